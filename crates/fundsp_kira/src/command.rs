@@ -0,0 +1,356 @@
+//! Control tracks from any system
+//!
+//! [`FundspAudioOutput`] isn't `Send`, so systems that touch it can't run in
+//! parallel. [`FundspCommands`] only queues what should happen, and one exclusive
+//! system applies the queue every frame. Commands that can't be applied are
+//! reported as [`FundspCommandFailed`] events.
+
+use crate::plugin::machine_loading;
+use crate::{FundspAudioOutput, Machine, MachinedHandle, NoAudioOutputs, Track};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use generic_array::ArrayLength;
+use kira::manager::error::PlaySoundError;
+use kira::tween::Tween;
+use kira::CommandError;
+use std::any::{type_name, TypeId};
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+/// Something to do to a track
+#[derive(Debug, Clone)]
+pub enum FundspCommand {
+    /// Play this machine on a new voice of the track once it is loaded, controlled by the handle
+    Play(Handle<Machine>, MachinedHandle),
+    Stop,
+    Pause,
+    Resume,
+    /// Fade the track's volume, as an amplitude
+    SetVolume(f32, Tween),
+    /// Pan the track, from -1.0 for fully left to 1.0 for fully right
+    SetPanning(f32, Tween),
+    /// Speed up or slow down the track
+    SetRate(f32, Tween),
+    /// Set a parameter of every voice the track is playing
+    SetParam {
+        name: String,
+        value: f32,
+    },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum FundspCommandError {
+    #[error("no track was added for {0}")]
+    UnknownTrack(&'static str),
+    #[error("machine failed to load, or isn't being loaded")]
+    MachineNotLoaded,
+    #[error("all {0} voices are playing")]
+    NoFreeVoice(usize),
+    #[error("what is playing has no parameter {0:?}")]
+    UnknownParam(String),
+    /// Includes running out of kira's command capacity, see [`FundspBackendSettings`](crate::FundspBackendSettings)
+    #[error(transparent)]
+    Play(#[from] PlaySoundError<NoAudioOutputs>),
+    /// Includes running out of kira's command capacity
+    #[error(transparent)]
+    Command(#[from] CommandError),
+}
+
+/// Sent when a queued command couldn't be applied
+#[derive(Event, Debug)]
+pub struct FundspCommandFailed {
+    /// The type name of the track's marker
+    pub track: &'static str,
+    pub command: FundspCommand,
+    pub error: FundspCommandError,
+}
+
+/// Queues [`FundspCommand`]s, without needing exclusive access to anything
+#[derive(SystemParam)]
+pub struct FundspCommands<'w> {
+    queue: Res<'w, FundspCommandQueue>,
+}
+
+impl FundspCommands<'_> {
+    /// Queue `command` for the track marked by `T`
+    pub fn push<T: Resource>(&self, command: FundspCommand) {
+        self.queue.push(QueuedCommand {
+            track: TypeId::of::<T>(),
+            track_name: type_name::<T>(),
+            command,
+        });
+    }
+
+    /// Play `machine` on a new voice, returning a handle to control just that voice
+    pub fn play<T: Resource>(&self, machine: Handle<Machine>) -> MachinedHandle {
+        let handle = MachinedHandle::default();
+        self.push::<T>(FundspCommand::Play(machine, handle.clone()));
+        handle
+    }
+
+    pub fn stop<T: Resource>(&self) {
+        self.push::<T>(FundspCommand::Stop);
+    }
+
+    pub fn pause<T: Resource>(&self) {
+        self.push::<T>(FundspCommand::Pause);
+    }
+
+    pub fn resume<T: Resource>(&self) {
+        self.push::<T>(FundspCommand::Resume);
+    }
+
+    pub fn set_volume<T: Resource>(&self, volume: f32) {
+        self.tween_volume::<T>(volume, Tween::default());
+    }
+
+    pub fn tween_volume<T: Resource>(&self, volume: f32, tween: Tween) {
+        self.push::<T>(FundspCommand::SetVolume(volume, tween));
+    }
+
+    pub fn set_panning<T: Resource>(&self, panning: f32) {
+        self.tween_panning::<T>(panning, Tween::default());
+    }
+
+    pub fn tween_panning<T: Resource>(&self, panning: f32, tween: Tween) {
+        self.push::<T>(FundspCommand::SetPanning(panning, tween));
+    }
+
+    pub fn set_rate<T: Resource>(&self, rate: f32) {
+        self.tween_rate::<T>(rate, Tween::default());
+    }
+
+    pub fn tween_rate<T: Resource>(&self, rate: f32, tween: Tween) {
+        self.push::<T>(FundspCommand::SetRate(rate, tween));
+    }
+
+    pub fn set_param<T: Resource>(&self, name: impl Into<String>, value: f32) {
+        self.push::<T>(FundspCommand::SetParam {
+            name: name.into(),
+            value,
+        });
+    }
+}
+
+struct QueuedCommand {
+    track: TypeId,
+    track_name: &'static str,
+    command: FundspCommand,
+}
+
+/// Whether a command was applied, or should wait for another frame
+enum Applied {
+    Done,
+    NotYet,
+}
+
+type ApplyCommand = fn(&mut World, &FundspCommand) -> Result<Applied, FundspCommandError>;
+
+/// The commands waiting to be applied, and how to apply them to each track
+#[derive(Resource, Default)]
+pub(crate) struct FundspCommandQueue {
+    pending: Mutex<Vec<QueuedCommand>>,
+    tracks: HashMap<TypeId, ApplyCommand>,
+}
+
+impl FundspCommandQueue {
+    fn push(&self, command: QueuedCommand) {
+        self.pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(command);
+    }
+
+    /// Let commands be applied to the track marked by `T`
+    pub(crate) fn add_track<T: Resource, N: ArrayLength>(&mut self) {
+        self.tracks
+            .insert(TypeId::of::<T>(), apply_track_command::<T, N>);
+    }
+}
+
+pub(crate) fn apply_commands(world: &mut World) {
+    let (pending, tracks) = {
+        let mut queue = world.resource_mut::<FundspCommandQueue>();
+        let pending = std::mem::take(
+            queue
+                .pending
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        if pending.is_empty() {
+            return;
+        }
+        (pending, queue.tracks.clone())
+    };
+
+    let mut waiting = vec![];
+    for queued in pending {
+        let applied = match tracks.get(&queued.track) {
+            Some(apply) => apply(world, &queued.command),
+            None => Err(FundspCommandError::UnknownTrack(queued.track_name)),
+        };
+        match applied {
+            Ok(Applied::Done) => {}
+            Ok(Applied::NotYet) => waiting.push(queued),
+            Err(error) => {
+                warn!(
+                    "Failed to apply {:?} to {}: {error}",
+                    queued.command, queued.track_name
+                );
+                world.send_event(FundspCommandFailed {
+                    track: queued.track_name,
+                    command: queued.command,
+                    error,
+                });
+            }
+        }
+    }
+
+    // Keep the order commands were queued in
+    let mut queue = world.resource_mut::<FundspCommandQueue>();
+    let pending = queue
+        .pending
+        .get_mut()
+        .unwrap_or_else(PoisonError::into_inner);
+    waiting.append(pending);
+    *pending = waiting;
+}
+
+fn apply_track_command<T: Resource, N: ArrayLength>(
+    world: &mut World,
+    command: &FundspCommand,
+) -> Result<Applied, FundspCommandError> {
+    // Tracks are added at startup
+    if !world.contains_resource::<Track<T, N>>() {
+        return Ok(Applied::NotYet);
+    }
+    world.resource_scope(|world, mut track: Mut<Track<T, N>>| {
+        match command {
+            FundspCommand::Play(machine, handle) => {
+                return world.resource_scope(|world, machines: Mut<Assets<Machine>>| {
+                    let Some(loaded) = machines.get(machine) else {
+                        let server = world.get_resource::<AssetServer>();
+                        return if machine_loading(server, machine) {
+                            Ok(Applied::NotYet)
+                        } else {
+                            handle.never_played();
+                            Err(FundspCommandError::MachineNotLoaded)
+                        };
+                    };
+                    world.non_send_resource_mut::<FundspAudioOutput>().play(
+                        loaded,
+                        &mut *track,
+                        handle.clone(),
+                    )?;
+                    Ok(Applied::Done)
+                });
+            }
+            FundspCommand::Stop => track.stop(),
+            FundspCommand::Pause => track.pause(),
+            FundspCommand::Resume => track.resume(),
+            FundspCommand::SetVolume(volume, tween) => track.tween_volume(*volume, *tween)?,
+            FundspCommand::SetPanning(panning, tween) => track.tween_panning(*panning, *tween)?,
+            FundspCommand::SetRate(rate, tween) => track.tween_rate(*rate, *tween)?,
+            FundspCommand::SetParam { name, value } => {
+                if !track.set_param(name, *value) {
+                    return Err(FundspCommandError::UnknownParam(name.clone()));
+                }
+            }
+        }
+        Ok(Applied::Done)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_commands, FundspCommand, FundspCommandError, FundspCommandFailed, FundspCommandQueue,
+        FundspCommands,
+    };
+    use crate::plugin::Voice;
+    use crate::{Machine, MachineParams, MachinedHandle, Track, Trackable};
+    use assert2::{check, let_assert};
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::*;
+    use generic_array::typenum::U4;
+
+    #[derive(Resource)]
+    struct Music;
+
+    #[derive(Resource)]
+    struct Unknown;
+
+    fn world() -> World {
+        let mut world = World::new();
+        let mut queue = FundspCommandQueue::default();
+        queue.add_track::<Music, U4>();
+        world.insert_resource(queue);
+        world.init_resource::<Events<FundspCommandFailed>>();
+        world.init_resource::<Assets<Machine>>();
+        world.insert_resource(Track::<Music, U4>::default());
+        world
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut world = world();
+        let active = MachinedHandle::default();
+        // Playing something without parameters
+        active.start(&MachineParams::default());
+        world.resource_mut::<Track<Music, U4>>().voices.push(Voice {
+            handle: active.clone(),
+            taps: Trackable::default(),
+        });
+
+        world.run_system_once(|commands: FundspCommands| {
+            commands.pause::<Music>();
+            commands.set_volume::<Music>(0.5);
+            commands.set_panning::<Music>(-2.0);
+            commands.set_param::<Music>("pitch", 2.0);
+            commands.stop::<Unknown>();
+        });
+        apply_commands(&mut world);
+
+        check!(active.is_paused());
+        check!(world.resource::<Track<Music, U4>>().volume() == 0.5);
+        check!(world.resource::<Track<Music, U4>>().panning() == -1.0);
+        let failed = world
+            .resource_mut::<Events<FundspCommandFailed>>()
+            .drain()
+            .collect::<Vec<_>>();
+        let_assert!([param, unknown] = failed.as_slice());
+        let_assert!(FundspCommandError::UnknownParam(name) = &param.error);
+        check!(name == "pitch");
+        check!(matches!(unknown.error, FundspCommandError::UnknownTrack(_)));
+        check!(matches!(unknown.command, FundspCommand::Stop));
+
+        world.run_system_once(|commands: FundspCommands| commands.stop::<Music>());
+        apply_commands(&mut world);
+        check!(world.resource::<Track<Music, U4>>().voices.is_empty());
+        check!(active
+            .should_unload
+            .load(std::sync::atomic::Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_play_fails_without_machine() {
+        let mut world = world();
+        let machine = Handle::<Machine>::weak_from_u128(7);
+        let handle = world.run_system_once(move |commands: FundspCommands| {
+            commands.play::<Music>(machine.clone())
+        });
+        apply_commands(&mut world);
+        // Nothing is loading the machine, so the command isn't retried
+        let queue = world.resource::<FundspCommandQueue>();
+        check!(queue.pending.lock().unwrap().is_empty());
+        check!(handle.is_finished());
+        check!(!handle.is_started());
+        let failed = world
+            .resource_mut::<Events<FundspCommandFailed>>()
+            .drain()
+            .collect::<Vec<_>>();
+        let_assert!([failed] = failed.as_slice());
+        check!(matches!(failed.error, FundspCommandError::MachineNotLoaded));
+    }
+}
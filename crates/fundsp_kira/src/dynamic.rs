@@ -0,0 +1,202 @@
+//! Tracks made and dropped at run-time
+//!
+//! Typed [`Track`]s need a marker type each, and are added when the app is built.
+//! [`DynamicTracks`] are [`Track`]s too, but are looked up by a name or an entity,
+//! so UI-defined channels or one per enemy don't need new types.
+
+use crate::plugin::{DefaultBufferLength, FundspAudioOutput, Track, TrackSettings};
+use crate::Machine;
+
+use bevy::ecs::entity::Entities;
+use bevy::prelude::*;
+use generic_array::ArrayLength;
+use std::collections::HashMap;
+
+/// Marks tracks in [`DynamicTracks`]
+pub struct Dynamic;
+
+pub type DynamicTrack<N = DefaultBufferLength> = Track<Dynamic, N>;
+
+/// What a [`DynamicTrack`] is found by
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TrackKey {
+    Name(String),
+    /// Dropped once the entity is despawned
+    Entity(Entity),
+}
+
+impl From<&str> for TrackKey {
+    fn from(name: &str) -> Self {
+        Self::Name(name.to_owned())
+    }
+}
+
+impl From<String> for TrackKey {
+    fn from(name: String) -> Self {
+        Self::Name(name)
+    }
+}
+
+impl From<Entity> for TrackKey {
+    fn from(entity: Entity) -> Self {
+        Self::Entity(entity)
+    }
+}
+
+/// [`DynamicTrack`]s by [`TrackKey`], keeping `N` recent samples each
+///
+/// New tracks play to kira's main track until their sub-track is made, at the end of the frame.
+#[derive(Resource)]
+pub struct DynamicTracks<N: ArrayLength = DefaultBufferLength> {
+    tracks: HashMap<TrackKey, DynamicTrack<N>>,
+    /// Tracks still waiting for their sub-track
+    unattached: Vec<(TrackKey, TrackSettings)>,
+}
+
+impl<N: ArrayLength> Default for DynamicTracks<N> {
+    fn default() -> Self {
+        Self {
+            tracks: HashMap::new(),
+            unattached: Vec::new(),
+        }
+    }
+}
+
+impl<N: ArrayLength> DynamicTracks<N> {
+    /// Make a track under `key`, stopping any track that was already there
+    pub fn create(
+        &mut self,
+        key: impl Into<TrackKey>,
+        settings: TrackSettings,
+    ) -> &mut DynamicTrack<N> {
+        let key = key.into();
+        if let Some(mut previous) = self.tracks.remove(&key) {
+            previous.stop();
+        }
+        self.unattached.retain(|(unattached, _)| *unattached != key);
+        let track = Track::new(&settings, kira::OutputDestination::MAIN_TRACK);
+        self.unattached.push((key.clone(), settings));
+        self.tracks.entry(key).or_insert(track)
+    }
+
+    pub fn get(&self, key: impl Into<TrackKey>) -> Option<&DynamicTrack<N>> {
+        self.tracks.get(&key.into())
+    }
+
+    pub fn get_mut(&mut self, key: impl Into<TrackKey>) -> Option<&mut DynamicTrack<N>> {
+        self.tracks.get_mut(&key.into())
+    }
+
+    /// The track under `key`, made with `settings` if there isn't one
+    pub fn get_or_create(
+        &mut self,
+        key: impl Into<TrackKey>,
+        settings: TrackSettings,
+    ) -> &mut DynamicTrack<N> {
+        let key = key.into();
+        if self.tracks.contains_key(&key) {
+            return self.tracks.get_mut(&key).unwrap();
+        }
+        self.create(key, settings)
+    }
+
+    /// Stop and drop the track under `key`, returning whether there was one
+    pub fn remove(&mut self, key: impl Into<TrackKey>) -> bool {
+        let key = key.into();
+        self.unattached.retain(|(unattached, _)| *unattached != key);
+        match self.tracks.remove(&key) {
+            Some(mut track) => {
+                track.stop();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, key: impl Into<TrackKey>) -> bool {
+        self.tracks.contains_key(&key.into())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&TrackKey, &DynamicTrack<N>)> {
+        self.tracks.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    /// Drop the tracks of entities that have been despawned
+    fn forget_despawned(&mut self, entities: &Entities) {
+        let despawned =
+            |key: &TrackKey| matches!(key, TrackKey::Entity(entity) if !entities.contains(*entity));
+        self.unattached.retain(|(key, _)| !despawned(key));
+        self.tracks.retain(|key, track| {
+            if despawned(key) {
+                track.stop();
+            }
+            !despawned(key)
+        });
+    }
+}
+
+pub(crate) fn update_dynamic_tracks<N: ArrayLength>(
+    mut output: NonSendMut<FundspAudioOutput>,
+    mut tracks: ResMut<DynamicTracks<N>>,
+    machines: Res<Assets<Machine>>,
+    server: Option<Res<AssetServer>>,
+    entities: &Entities,
+) {
+    let tracks = &mut *tracks;
+    tracks.forget_despawned(entities);
+    for (key, settings) in tracks.unattached.drain(..) {
+        if let Some(track) = tracks.tracks.get_mut(&key) {
+            output.attach(track, &settings);
+        }
+    }
+    for track in tracks.tracks.values_mut() {
+        output.play_next_machines(track, &machines, server.as_deref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DynamicTracks, TrackKey};
+    use crate::plugin::TrackSettings;
+    use assert2::check;
+    use bevy::prelude::*;
+
+    #[test]
+    fn test_dynamic_tracks() {
+        let mut world = World::new();
+        let enemy = world.spawn_empty().id();
+        let mut tracks = DynamicTracks::<generic_array::typenum::U4>::default();
+
+        tracks.create("ui", TrackSettings::default());
+        tracks
+            .create(enemy, TrackSettings::default())
+            .set_volume(0.5)
+            .unwrap();
+        check!(tracks.len() == 2);
+        check!(tracks.get(enemy).unwrap().volume() == 0.5);
+
+        // Making it again starts over
+        tracks.get_or_create(enemy, TrackSettings::default());
+        check!(tracks.get(enemy).unwrap().volume() == 0.5);
+        tracks.create(enemy, TrackSettings::default());
+        check!(tracks.get(enemy).unwrap().volume() == 1.0);
+
+        world.despawn(enemy);
+        tracks.forget_despawned(world.entities());
+        check!(!tracks.contains(enemy));
+        check!(tracks.contains(TrackKey::from("ui")));
+        check!(tracks.unattached == [(TrackKey::from("ui"), TrackSettings::default())]);
+
+        check!(tracks.remove("ui"));
+        check!(!tracks.remove("ui"));
+        check!(tracks.is_empty());
+    }
+}
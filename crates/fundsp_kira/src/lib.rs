@@ -0,0 +1,780 @@
+//! Stuff to connect fundsp and kira
+//!
+//! A [`Machine`] is a fundsp program, which plays on kira as a [`FundspSound`].
+//! Its named parameters can be set while it plays, through a [`MachinedHandle`].
+//! With the `bevy` feature, [`FundspAudioPlugin`] loads machines as assets and
+//! plays them on [`Track`]s, which any system can control with [`FundspCommands`].
+//! With the `sfxr` feature, [`sfxr`] describes simple sound effects in RON, and with
+//! the `tweening` feature, [`tweening`] fades tracks with bevy_tweening.
+
+#[cfg(feature = "bevy")]
+mod command;
+#[cfg(feature = "bevy")]
+mod dynamic;
+pub mod mixer;
+#[cfg(feature = "bevy")]
+mod plugin;
+#[cfg(feature = "sfxr")]
+pub mod sfxr;
+#[cfg(feature = "bevy")]
+pub mod spatial;
+#[cfg(feature = "tweening")]
+pub mod tweening;
+
+#[cfg(feature = "bevy")]
+pub use command::{FundspCommand, FundspCommandError, FundspCommandFailed, FundspCommands};
+#[cfg(feature = "bevy")]
+pub use dynamic::{Dynamic, DynamicTrack, DynamicTracks, TrackKey};
+#[cfg(feature = "bevy")]
+pub use plugin::{
+    DefaultBufferLength, DefaultTrack, FundspAudioApp, FundspAudioOutput, FundspAudioPlugin,
+    FundspAudioSystemSet, FundspBackendSettings, MainTrack, Track, TrackSettings, VoiceStealing,
+};
+
+use fundsp::prelude::*;
+#[cfg(feature = "bevy")]
+use generic_array::functional::FunctionalSequence as _;
+use generic_array::{sequence::GenericSequence, ArrayLength, GenericArray};
+use kira::sound::{Sound, SoundData};
+use std::{
+    any::Any,
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::Duration,
+};
+
+#[derive(Debug, Clone)]
+struct ChannelDetails<N: ArrayLength> {
+    rms: GenericArray<Arc<AtomicU32>, N>,
+    samples: GenericArray<Arc<AtomicU32>, N>,
+}
+
+impl<N: ArrayLength> ChannelDetails<N> {
+    fn store_sample(&self, sample: f32) {
+        for i in 0..(N::to_usize() - 1) {
+            self.samples[i].swap(
+                self.samples[i + 1].load(Ordering::Acquire),
+                Ordering::AcqRel,
+            );
+        }
+        self.samples[N::to_usize() - 1].store(sample.to_bits(), Ordering::Release);
+    }
+
+    fn store_rms(&self, rms: f32) {
+        for i in 0..(N::to_usize() - 1) {
+            self.rms[i].swap(self.rms[i + 1].load(Ordering::Acquire), Ordering::AcqRel);
+        }
+        self.rms[N::to_usize() - 1].store(rms.to_bits(), Ordering::Release);
+    }
+
+    #[cfg(feature = "bevy")]
+    fn latest_rms(&self) -> f32 {
+        f32::from_bits(self.rms[N::to_usize() - 1].load(Ordering::Acquire))
+    }
+
+    #[cfg(feature = "bevy")]
+    fn samples(&self) -> GenericArray<f32, N> {
+        <GenericArray<_, N>>::clone(&self.samples)
+            .map(|bits| f32::from_bits(bits.load(Ordering::Acquire)))
+    }
+
+    #[cfg(feature = "bevy")]
+    fn rms(&self) -> GenericArray<f32, N> {
+        <GenericArray<_, N>>::clone(&self.rms)
+            .map(|bits| f32::from_bits(bits.load(Ordering::Acquire)))
+    }
+
+    fn empty_atomics() -> GenericArray<Arc<AtomicU32>, N> {
+        GenericArray::generate(|_| Arc::new(AtomicU32::new(0.0f32.to_bits())))
+    }
+}
+
+impl<N: ArrayLength> Default for ChannelDetails<N> {
+    fn default() -> Self {
+        Self {
+            rms: Self::empty_atomics(),
+            samples: Self::empty_atomics(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Trackable<T, N: ArrayLength> {
+    left_channel: ChannelDetails<N>,
+    right_channel: ChannelDetails<N>,
+    _marker: PhantomData<T>,
+}
+
+impl<T, N: ArrayLength> Clone for Trackable<T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            left_channel: self.left_channel.clone(),
+            right_channel: self.right_channel.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, N: ArrayLength> Default for Trackable<T, N> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+            left_channel: ChannelDetails::default(),
+            right_channel: ChannelDetails::default(),
+        }
+    }
+}
+
+/// Builds a fundsp program that reads its parameters
+type BuildMachine = dyn Fn(&MachineParams) -> Box<dyn AudioUnit32> + Send + Sync;
+
+/// A fundsp program to play
+///
+/// Programs made with [`Machine::with_params`] are built afresh for every play, so
+/// each playing sound's parameters can be set on its own [`MachinedHandle`].
+#[derive(Clone)]
+#[cfg_attr(feature = "bevy", derive(bevy::asset::Asset, bevy::reflect::TypePath))]
+pub struct Machine {
+    /// The fundsp audio program
+    ///
+    /// With parameters, this is only a copy built with their defaults, and isn't played.
+    pub machine: Box<dyn AudioUnit32>,
+    /// The desired noise floor (RMS)
+    pub noise_floor: f32,
+    /// Userdata (generally how the Machine was created)
+    // generally immutable, after all, the Machine has been made
+    userdata: Option<Arc<dyn Any + Send + Sync>>,
+    /// Builds the program, if it has parameters
+    build: Option<Arc<BuildMachine>>,
+    /// The parameters the program reads, and where they start
+    params: HashMap<String, f32>,
+}
+
+impl Machine {
+    pub const DEFAULT_NOISE_FLOOR: f32 = 0.001;
+
+    pub fn new<T: AudioUnit32 + 'static>(machine: T) -> Self {
+        Self::with_noise_floor(machine, Self::DEFAULT_NOISE_FLOOR)
+    }
+
+    pub fn with_noise_floor<T: AudioUnit32 + 'static>(machine: T, noise_floor: f32) -> Self {
+        Self {
+            machine: Box::new(machine),
+            noise_floor,
+            userdata: None,
+            build: None,
+            params: HashMap::new(),
+        }
+    }
+
+    /// A program with named parameters, which can be set while it plays
+    ///
+    /// `build` makes the program, reading the parameters with [`MachineParams::var`].
+    /// Each parameter starts at its default, and glides to new values over a few
+    /// milliseconds so changing it doesn't click.
+    ///
+    /// # Panics
+    ///
+    /// If `build` reads a parameter that isn't declared in `params`.
+    pub fn with_params<S, U>(
+        params: impl IntoIterator<Item = (S, f32)>,
+        build: impl Fn(&MachineParams) -> U + Send + Sync + 'static,
+    ) -> Self
+    where
+        S: Into<String>,
+        U: AudioUnit32 + 'static,
+    {
+        let params: HashMap<String, f32> = params
+            .into_iter()
+            .map(|(name, value)| (name.into(), value))
+            .collect();
+        let build: Arc<BuildMachine> =
+            Arc::new(move |params: &MachineParams| -> Box<dyn AudioUnit32> {
+                Box::new(build(params))
+            });
+        Self {
+            machine: build(&MachineParams::new(&params)),
+            noise_floor: Self::DEFAULT_NOISE_FLOOR,
+            userdata: None,
+            build: Some(build),
+            params,
+        }
+    }
+
+    pub fn with_userdata<U: Any + Send + Sync>(self, userdata: U) -> Self {
+        Self {
+            userdata: Some(Arc::new(userdata)),
+            ..self
+        }
+    }
+
+    pub fn userdata(&self) -> Option<&Arc<dyn Any + Send + Sync>> {
+        self.userdata.as_ref()
+    }
+
+    /// The names of the program's parameters, and where they start
+    pub fn params(&self) -> impl Iterator<Item = (&str, f32)> {
+        self.params
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+    }
+
+    /// A program to play, and the parameters it reads
+    fn instance(&self) -> (Box<dyn AudioUnit32>, MachineParams) {
+        match &self.build {
+            Some(build) => {
+                let params = MachineParams::new(&self.params);
+                (build(&params), params)
+            }
+            None => (self.machine.clone(), MachineParams::default()),
+        }
+    }
+}
+
+/// One parameter of a playing program
+#[derive(Clone)]
+struct Param {
+    /// Where the parameter was set to
+    target: Shared<f32>,
+    /// What the program reads, gliding toward `target`
+    current: Shared<f32>,
+}
+
+/// The parameters of one play of a [`Machine`]
+#[derive(Clone, Default)]
+pub struct MachineParams {
+    params: HashMap<String, Param>,
+}
+
+impl MachineParams {
+    fn new(defaults: &HashMap<String, f32>) -> Self {
+        let params = defaults
+            .iter()
+            .map(|(name, value)| {
+                let param = Param {
+                    target: Shared::new(*value),
+                    current: Shared::new(*value),
+                };
+                (name.clone(), param)
+            })
+            .collect();
+        Self { params }
+    }
+
+    /// A node that outputs the parameter `name`, smoothed
+    ///
+    /// # Panics
+    ///
+    /// If the machine has no parameter `name`.
+    pub fn var(&self, name: &str) -> An<Var<f32>> {
+        match self.params.get(name) {
+            Some(param) => var(&param.current),
+            None => panic!("machine has no parameter {name:?}"),
+        }
+    }
+
+    /// Set parameter `name` to glide to `value`, returning whether there is one
+    fn set(&self, name: &str, value: f32) -> bool {
+        self.params
+            .get(name)
+            .map(|param| param.target.set_value(value))
+            .is_some()
+    }
+
+    /// Set parameter `name` to `value` straight away
+    fn jump(&self, name: &str, value: f32) {
+        if let Some(param) = self.params.get(name) {
+            param.target.set_value(value);
+            param.current.set_value(value);
+        }
+    }
+
+    /// Move every parameter `amount` of the way to where it was set
+    fn glide(&self, amount: f32) {
+        for param in self.params.values() {
+            let current = param.current.value();
+            let target = param.target.value();
+            if current != target {
+                let next = current + (target - current) * amount;
+                // Land, rather than creeping up on the target forever
+                let next = if (target - next).abs() <= f32::EPSILON * target.abs().max(1.0) {
+                    target
+                } else {
+                    next
+                };
+                param.current.set_value(next);
+            }
+        }
+    }
+}
+
+/// Parameters set on a [`MachinedHandle`]
+enum HandleParams {
+    /// Values to start the sound with, once it is played
+    Pending(Vec<(String, f32)>),
+    Started(MachineParams),
+}
+
+impl Default for HandleParams {
+    fn default() -> Self {
+        Self::Pending(Vec::new())
+    }
+}
+
+// TODO This should be associated with a track n stuff.
+pub struct Machined<T, N: ArrayLength> {
+    node: Box<dyn AudioUnit32>,
+    params: MachineParams,
+    sample_rate: f64,
+    settings: MachinedSettings,
+    handle: MachinedHandle,
+    trackable: Trackable<T, N>,
+}
+
+// TODO This should be determined by the track
+#[derive(Clone, Debug, Default)]
+struct MachinedSettings {
+    output: kira::OutputDestination,
+    noise_floor: f32,
+    /// Scales how fast the sound plays, if set
+    rate: Option<kira::modulator::ModulatorId>,
+}
+
+#[derive(Clone)]
+pub struct MachinedHandle {
+    should_unload: Arc<AtomicBool>,
+    /// Set by the sound once it has stopped
+    finished: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    /// Left and right gains, as bits
+    gains: Arc<[AtomicU32; 2]>,
+    /// Volume, as bits
+    volume: Arc<AtomicU32>,
+    params: Arc<Mutex<HandleParams>>,
+}
+
+impl Default for MachinedHandle {
+    fn default() -> Self {
+        Self {
+            should_unload: Arc::new(AtomicBool::new(false)),
+            finished: Arc::new(AtomicBool::new(false)),
+            paused: Arc::new(AtomicBool::new(false)),
+            gains: Arc::new([
+                AtomicU32::new(1.0f32.to_bits()),
+                AtomicU32::new(1.0f32.to_bits()),
+            ]),
+            volume: Arc::new(AtomicU32::new(1.0f32.to_bits())),
+            params: Arc::default(),
+        }
+    }
+}
+
+impl std::fmt::Debug for MachinedHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MachinedHandle")
+            .field("should_unload", &self.should_unload)
+            .field("finished", &self.finished)
+            .field("paused", &self.paused)
+            .field("gains", &self.gains())
+            .field("volume", &self.volume())
+            .field("started", &self.is_started())
+            .finish()
+    }
+}
+
+impl MachinedHandle {
+    /// Stops any sounds associated with this [`Trackable`]
+    pub fn unload(&self) {
+        self.should_unload.store(true, Ordering::SeqCst)
+    }
+
+    /// Whether the sound has stopped, by being unloaded or going quiet
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+
+    /// Scale the left and right channels of the sound
+    ///
+    /// The sound glides to new gains over a few milliseconds, so they can change every frame.
+    pub fn set_gains(&self, (left, right): (f32, f32)) {
+        self.gains[0].store(left.to_bits(), Ordering::Release);
+        self.gains[1].store(right.to_bits(), Ordering::Release);
+    }
+
+    pub fn gains(&self) -> (f32, f32) {
+        (
+            f32::from_bits(self.gains[0].load(Ordering::Acquire)),
+            f32::from_bits(self.gains[1].load(Ordering::Acquire)),
+        )
+    }
+
+    /// Scale both channels of the sound, on top of its gains
+    pub fn set_volume(&self, volume: f32) {
+        self.volume.store(volume.to_bits(), Ordering::Release);
+    }
+
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.volume.load(Ordering::Acquire))
+    }
+
+    /// Hold the sound where it is, in silence, until it is resumed
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Release);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Release);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Set the machine's parameter `name`, returning whether it has one
+    ///
+    /// Playing sounds glide to the new value. Before the sound starts, any name is
+    /// taken, and the sound starts with the value if it has such a parameter.
+    pub fn set_param(&self, name: &str, value: f32) -> bool {
+        match &mut *self.params.lock().unwrap_or_else(PoisonError::into_inner) {
+            HandleParams::Pending(values) => {
+                values.retain(|(pending, _)| pending != name);
+                values.push((name.to_owned(), value));
+                true
+            }
+            HandleParams::Started(params) => params.set(name, value),
+        }
+    }
+
+    /// Whether the sound has been played
+    pub fn is_started(&self) -> bool {
+        matches!(
+            *self.params.lock().unwrap_or_else(PoisonError::into_inner),
+            HandleParams::Started(_)
+        )
+    }
+
+    /// Hand the sound's parameters to the handle, with any values set before it started
+    fn start(&self, params: &MachineParams) {
+        let mut state = self.params.lock().unwrap_or_else(PoisonError::into_inner);
+        if let HandleParams::Pending(values) = &*state {
+            for (name, value) in values {
+                params.jump(name, *value);
+            }
+        }
+        *state = HandleParams::Started(params.clone());
+    }
+
+    /// Mark a sound that will never play as finished
+    #[cfg(feature = "bevy")]
+    fn never_played(&self) {
+        self.finished.store(true, Ordering::Release);
+    }
+}
+
+impl<T, N: ArrayLength> Machined<T, N> {
+    /// Sound data for playing `machine` on `output`, to hand to kira's `AudioManager::play`
+    pub fn from_machine(
+        machine: &Machine,
+        output: kira::OutputDestination,
+        sample_rate: f64,
+    ) -> Self {
+        Self::new(
+            machine,
+            output,
+            sample_rate,
+            Trackable::default(),
+            MachinedHandle::default(),
+        )
+    }
+
+    /// Start `machine`, to be controlled by `handle`
+    fn new(
+        machine: &Machine,
+        output: kira::OutputDestination,
+        sample_rate: f64,
+        trackable: Trackable<T, N>,
+        handle: MachinedHandle,
+    ) -> Self {
+        let (mut node, params) = machine.instance();
+        // Set the node sample rate from the track
+        node.set_sample_rate(sample_rate);
+        handle.start(&params);
+
+        Self {
+            node,
+            params,
+            sample_rate,
+            settings: MachinedSettings {
+                output,
+                noise_floor: machine.noise_floor,
+                rate: None,
+            },
+            handle,
+            trackable,
+        }
+    }
+}
+
+impl<Track, N> SoundData for Machined<Track, N>
+where
+    Track: Send + 'static,
+    N: ArrayLength,
+{
+    type Error = NoAudioOutputs;
+    // TODO Make this an actual handle that can control the state of the FundspSound
+    type Handle = MachinedHandle;
+
+    fn into_sound(mut self) -> Result<(Box<dyn Sound>, Self::Handle), Self::Error> {
+        self.node.allocate();
+        Ok((
+            Box::new(FundspSound::new(
+                self.node,
+                self.params,
+                self.sample_rate,
+                self.settings,
+                self.trackable,
+                self.handle.clone(),
+            )?),
+            self.handle,
+        ))
+    }
+}
+
+pub struct FundspSound<Track, N: ArrayLength> {
+    node: Box<dyn AudioUnit32>,
+    params: MachineParams,
+    /// How far parameters glide toward where they were set, each sample
+    param_glide: f32,
+    sample_time: Duration,
+    elapsed: Duration,
+
+    settings: MachinedSettings,
+    rms_left: Shared<f32>,
+    rms_right: Shared<f32>,
+    monitor_left: An<Monitor<f32>>,
+    monitor_right: An<Monitor<f32>>,
+
+    trackable: Trackable<Track, N>,
+    handle: MachinedHandle,
+    /// The gains being applied, on their way to the handle's
+    gains: (f32, f32),
+
+    buffer: [kira::dsp::Frame; 4],
+}
+
+/// How long it takes gains to get most of the way to a new value, in seconds
+const GAIN_SMOOTHING: f64 = 0.005;
+
+/// How long it takes parameters to get most of the way to a new value, in seconds
+const PARAM_SMOOTHING: f64 = 0.02;
+
+#[derive(thiserror::Error, Debug)]
+#[error("No audio outputs from given AudioUnit32")]
+pub struct NoAudioOutputs;
+
+impl<Track, N: ArrayLength> FundspSound<Track, N> {
+    #[inline(always)]
+    fn make_frame((left, right): (f32, f32)) -> kira::dsp::Frame {
+        kira::dsp::Frame { left, right }
+    }
+
+    fn new(
+        mut node: Box<dyn AudioUnit32>,
+        params: MachineParams,
+        sample_rate: f64,
+        settings: MachinedSettings,
+        trackable: Trackable<Track, N>,
+        handle: MachinedHandle,
+    ) -> Result<Self, NoAudioOutputs> {
+        let rms_left = Shared::new(0.0);
+        let rms_right = Shared::new(0.0);
+
+        // to start a new sound, fast-forward (and throw away)
+        // latency signals
+        if let Some(mut latency) = node.latency() {
+            while latency >= 1.0 {
+                latency -= 1.0;
+                _ = node.get_stereo();
+            }
+        } else {
+            // Return an error!
+            return Err(NoAudioOutputs);
+        }
+
+        Ok(FundspSound {
+            params,
+            param_glide: (1.0 - (-(sample_rate * PARAM_SMOOTHING).recip()).exp()) as f32,
+            sample_time: Duration::from_secs_f64(sample_rate.recip()),
+            elapsed: Duration::ZERO,
+            settings,
+            monitor_left: monitor(&rms_left, Meter::Rms(0.1)),
+            monitor_right: monitor(&rms_right, Meter::Rms(0.1)),
+
+            rms_left,
+            rms_right,
+
+            trackable,
+            gains: handle.gains(),
+            handle,
+            // Get the first four frames
+            buffer: [
+                // prev
+                kira::dsp::Frame::ZERO,
+                // current
+                Self::make_frame(node.get_stereo()),
+                // next_1
+                Self::make_frame(node.get_stereo()),
+                // next_2
+                Self::make_frame(node.get_stereo()),
+            ],
+            node,
+        })
+    }
+}
+
+impl<Track, N> Sound for FundspSound<Track, N>
+where
+    Track: Send,
+    N: ArrayLength,
+{
+    fn output_destination(&mut self) -> kira::OutputDestination {
+        self.settings.output
+    }
+
+    fn process(
+        &mut self,
+        dt: f64,
+        _clock_info_provider: &kira::clock::clock_info::ClockInfoProvider,
+        modulator_value_provider: &kira::modulator::value_provider::ModulatorValueProvider,
+    ) -> kira::dsp::Frame {
+        // Hold still, and let gains glide back in from silence on resuming
+        if self.handle.is_paused() {
+            self.gains = (0.0, 0.0);
+            return kira::dsp::Frame::ZERO;
+        }
+        // sample rate check
+        let mut frame = kira::dsp::interpolate_frame(
+            self.buffer[0],
+            self.buffer[1],
+            self.buffer[2],
+            self.buffer[3],
+            (self.elapsed.as_secs_f32() / self.sample_time.as_secs_f32()).clamp(0.0, 1.0),
+        );
+        let rate = self
+            .settings
+            .rate
+            .and_then(|rate| modulator_value_provider.get(rate))
+            .unwrap_or(1.0);
+        self.elapsed += Duration::from_secs_f64(dt * rate.max(0.0));
+        while self.elapsed > self.sample_time {
+            self.elapsed -= self.sample_time;
+            // push in a new frame, by shifting over everything else
+            for i in 0..self.buffer.len() - 1 {
+                self.buffer[i] = self.buffer[i + 1];
+            }
+            self.params.glide(self.param_glide);
+            self.buffer[self.buffer.len() - 1] = Self::make_frame(self.node.get_stereo());
+        }
+        // Process samples (from frame)
+        // Monitor samples
+        self.monitor_left.filter_mono(frame.left);
+        self.monitor_right.filter_mono(frame.right);
+        // Apply gains after monitoring, so quiet sounds aren't taken for finished ones
+        let (left, right) = self.handle.gains();
+        let volume = self.handle.volume();
+        let (left, right) = (left * volume, right * volume);
+        let glide = (1.0 - (-dt / GAIN_SMOOTHING).exp()) as f32;
+        self.gains.0 += (left - self.gains.0) * glide;
+        self.gains.1 += (right - self.gains.1) * glide;
+        frame.left *= self.gains.0;
+        frame.right *= self.gains.1;
+        // Report sample
+        self.trackable.left_channel.store_sample(frame.left);
+        self.trackable.right_channel.store_sample(frame.right);
+        frame
+    }
+
+    fn finished(&self) -> bool {
+        let should_unload = self.handle.should_unload.load(Ordering::Acquire);
+        if should_unload {
+            tracing::debug!("Stopping {self:p} due to unload!")
+        }
+        // Paused sounds are silent, but not finished
+        if self.handle.is_paused() {
+            self.handle.finished.store(should_unload, Ordering::Release);
+            return should_unload;
+        }
+
+        let left_rms = self.rms_left.value();
+        let right_rms = self.rms_right.value();
+        let noise_floor = self.settings.noise_floor;
+        tracing::trace!("RMS: {left_rms} {right_rms} NF {noise_floor}");
+
+        // Report RMS
+        self.trackable.left_channel.store_rms(left_rms);
+        self.trackable.right_channel.store_rms(right_rms);
+
+        // If we are quieter (on both channels!!) than the noise floor, assume we
+        // are finished an want to stop
+        let below_noise_floor = left_rms < noise_floor && right_rms < noise_floor;
+        if below_noise_floor {
+            tracing::debug!("Stopping {self:p} due to being quieter than the noise floor!");
+        }
+
+        let finished = below_noise_floor || should_unload;
+        self.handle.finished.store(finished, Ordering::Release);
+        finished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Machine, Machined, MachinedHandle, Trackable};
+    use assert2::check;
+    use fundsp::prelude::DEFAULT_SR;
+    use generic_array::typenum::U1;
+
+    #[test]
+    fn test_params() {
+        let machine = Machine::with_params([("pitch", 1.0)], |params| params.var("pitch"));
+        let handle = MachinedHandle::default();
+        check!(!handle.is_started());
+        check!(handle.set_param("pitch", 2.0));
+        let machined = Machined::<(), U1>::new(
+            &machine,
+            kira::OutputDestination::MAIN_TRACK,
+            DEFAULT_SR,
+            Trackable::default(),
+            handle.clone(),
+        );
+        check!(handle.is_started());
+        let mut node = machined.node;
+        // Starts with what was set before playing
+        check!(node.get_mono() == 2.0);
+        check!(!handle.set_param("volume", 1.0));
+
+        // Glides to new values, rather than jumping
+        check!(handle.set_param("pitch", 4.0));
+        check!(node.get_mono() == 2.0);
+        machined.params.glide(0.5);
+        check!(node.get_mono() == 3.0);
+        for _ in 0..100 {
+            machined.params.glide(0.5);
+        }
+        check!(node.get_mono() == 4.0);
+
+        // Every play has its own parameters
+        let mut other = Machined::<(), U1>::from_machine(
+            &machine,
+            kira::OutputDestination::MAIN_TRACK,
+            DEFAULT_SR,
+        );
+        check!(other.node.get_mono() == 1.0);
+        check!(other.handle.set_param("pitch", 8.0));
+        check!(node.get_mono() == 4.0);
+    }
+}
@@ -0,0 +1,564 @@
+//! Buses that tracks play into, with effects and sends
+//!
+//! A [`MixerSettings`] lists [`Bus`]es. Each bus is a kira sub-track, which plays
+//! into its parent bus (or kira's main track), sends some of itself to other buses,
+//! and runs its [`BusEffect`]s on what it hears. With the `config` feature, mixers
+//! can be written in RON:
+//!
+//! ```ron
+//! (
+//!     buses: [
+//!         (name: "reverb", effects: [Reverb(room_size: 20.0, time: 2.0, wet: 1.0)]),
+//!         (name: "music", volume: 0.6, sends: [(to: "reverb", level: 0.2)]),
+//!         (name: "sfx", sends: [(to: "reverb", level: 0.4)]),
+//!     ],
+//! )
+//! ```
+
+use fundsp::hacker32::*;
+use kira::{
+    clock::clock_info::ClockInfoProvider,
+    dsp::Frame,
+    manager::{backend::Backend, error::AddSubTrackError, AudioManager},
+    modulator::value_provider::ModulatorValueProvider,
+    track::{
+        effect::{Effect, EffectBuilder},
+        TrackBuilder, TrackHandle, TrackId, TrackRoutes,
+    },
+    Volume,
+};
+use std::collections::{HashMap, HashSet};
+
+/// The buses of a mixer
+///
+/// With the `bevy` feature, insert this before adding the
+/// [`FundspAudioPlugin`](crate::FundspAudioPlugin) to build it with the audio output.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Resource))]
+#[cfg_attr(feature = "config", derive(serde::Deserialize, serde::Serialize))]
+pub struct MixerSettings {
+    pub buses: Vec<Bus>,
+    /// The bus the [`MainTrack`](crate::MainTrack) plays into
+    #[cfg_attr(feature = "config", serde(default))]
+    pub main_track_bus: Option<String>,
+}
+
+impl MixerSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_bus(mut self, bus: Bus) -> Self {
+        self.buses.push(bus);
+        self
+    }
+
+    pub fn with_main_track_bus(self, bus: impl Into<String>) -> Self {
+        Self {
+            main_track_bus: Some(bus.into()),
+            ..self
+        }
+    }
+
+    #[cfg(feature = "config")]
+    pub fn from_ron(ron: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(ron)
+    }
+
+    /// Add every bus to `manager`, parents and send targets first
+    pub fn build<B: Backend>(
+        &self,
+        manager: &mut AudioManager<B>,
+    ) -> Result<HashMap<String, TrackHandle>, MixerError> {
+        let mut built = HashMap::new();
+        for bus in &self.buses {
+            self.build_bus(&bus.name, manager, &mut built, &mut HashSet::new())?;
+        }
+        Ok(built)
+    }
+
+    fn build_bus<B: Backend>(
+        &self,
+        name: &str,
+        manager: &mut AudioManager<B>,
+        built: &mut HashMap<String, TrackHandle>,
+        visiting: &mut HashSet<String>,
+    ) -> Result<TrackId, MixerError> {
+        if let Some(handle) = built.get(name) {
+            return Ok(handle.id());
+        }
+        let bus = self
+            .buses
+            .iter()
+            .find(|bus| bus.name == name)
+            .ok_or_else(|| MixerError::UnknownBus(name.to_owned()))?;
+        if !visiting.insert(name.to_owned()) {
+            return Err(MixerError::Cycle(name.to_owned()));
+        }
+
+        let mut routes = match &bus.parent {
+            Some(parent) => TrackRoutes::parent(self.build_bus(parent, manager, built, visiting)?),
+            None => TrackRoutes::new(),
+        };
+        for send in &bus.sends {
+            let to = self.build_bus(&send.to, manager, built, visiting)?;
+            routes = routes.with_route(to, Volume::Amplitude(send.level as f64));
+        }
+        let mut builder = TrackBuilder::new()
+            .volume(Volume::Amplitude(bus.volume as f64))
+            .routes(routes);
+        for effect in &bus.effects {
+            builder.add_effect(*effect);
+        }
+        let handle = manager.add_sub_track(builder)?;
+        let id = handle.id();
+        built.insert(name.to_owned(), handle);
+        visiting.remove(name);
+        Ok(id)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum MixerError {
+    #[error("no bus is named {0:?}")]
+    UnknownBus(String),
+    #[error("bus {0:?} routes back into itself")]
+    Cycle(String),
+    #[error(transparent)]
+    SubTrack(#[from] AddSubTrackError),
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+/// A sub-track that tracks and other buses play into
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize, serde::Serialize))]
+pub struct Bus {
+    pub name: String,
+    /// The bus this one plays into, instead of kira's main track
+    #[cfg_attr(feature = "config", serde(default))]
+    pub parent: Option<String>,
+    #[cfg_attr(feature = "config", serde(default = "one"))]
+    pub volume: f32,
+    #[cfg_attr(feature = "config", serde(default))]
+    pub sends: Vec<BusSend>,
+    /// Run in order, on everything the bus hears
+    #[cfg_attr(feature = "config", serde(default))]
+    pub effects: Vec<BusEffect>,
+}
+
+impl Bus {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            parent: None,
+            volume: one(),
+            sends: Vec::new(),
+            effects: Vec::new(),
+        }
+    }
+
+    pub fn with_parent(self, parent: impl Into<String>) -> Self {
+        Self {
+            parent: Some(parent.into()),
+            ..self
+        }
+    }
+
+    pub fn with_volume(self, volume: f32) -> Self {
+        Self { volume, ..self }
+    }
+
+    /// Also play into `to`, scaled by `level`
+    pub fn with_send(mut self, to: impl Into<String>, level: f32) -> Self {
+        self.sends.push(BusSend {
+            to: to.into(),
+            level,
+        });
+        self
+    }
+
+    pub fn with_effect(mut self, effect: BusEffect) -> Self {
+        self.effects.push(effect);
+        self
+    }
+}
+
+/// Some of a bus, played into another bus
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize, serde::Serialize))]
+pub struct BusSend {
+    pub to: String,
+    /// As an amplitude
+    pub level: f32,
+}
+
+/// Something done to everything a bus hears
+///
+/// `wet` mixes what the bus hears with the effect, from 0.0 for none of the effect
+/// to 1.0 for only the effect, as on a bus that is only sent to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize, serde::Serialize))]
+pub enum BusEffect {
+    /// fundsp's stereo reverb, with `room_size` in meters and `time` in seconds
+    Reverb {
+        room_size: f32,
+        time: f32,
+        wet: f32,
+    },
+    /// Echoes every `seconds`, each `feedback` times as loud as the last
+    Delay {
+        seconds: f32,
+        feedback: f32,
+        wet: f32,
+    },
+    Lowpass {
+        cutoff: f32,
+        q: f32,
+    },
+    /// Scales down what is louder than `threshold` decibels by `ratio`
+    ///
+    /// `attack` and `release` are how many seconds it takes to start and stop compressing.
+    Compressor {
+        threshold: f32,
+        ratio: f32,
+        attack: f32,
+        release: f32,
+    },
+}
+
+impl EffectBuilder for BusEffect {
+    type Handle = ();
+
+    fn build(self) -> (Box<dyn Effect>, Self::Handle) {
+        let dry = |wet: f32| multipass::<U2>() * (1.0 - wet);
+        let effect: Box<dyn Effect> = match self {
+            BusEffect::Reverb {
+                room_size,
+                time,
+                wet,
+            } => {
+                let reverb = reverb_stereo(room_size as f64, time as f64);
+                FundspEffect::boxed(dry(wet) & (reverb * wet))
+            }
+            BusEffect::Delay {
+                seconds,
+                feedback: level,
+                wet,
+            } => {
+                let echo = || delay(seconds);
+                FundspEffect::boxed(dry(wet) & (feedback((echo() | echo()) * level) * wet))
+            }
+            BusEffect::Lowpass { cutoff, q } => {
+                FundspEffect::boxed(lowpass_hz(cutoff, q) | lowpass_hz(cutoff, q))
+            }
+            // Looks at both channels at once, so it isn't a fundsp program
+            BusEffect::Compressor {
+                threshold,
+                ratio,
+                attack,
+                release,
+            } => Box::new(Compressor {
+                threshold: db_amp(threshold),
+                ratio: ratio.max(1.0),
+                attack,
+                release,
+                attack_glide: 1.0,
+                release_glide: 1.0,
+                envelope: 0.0,
+            }),
+        };
+        (effect, ())
+    }
+}
+
+/// Runs a stereo fundsp program on a kira track
+struct FundspEffect {
+    unit: Box<dyn AudioUnit32>,
+}
+
+impl FundspEffect {
+    /// `unit` goes from stereo to stereo
+    fn boxed(unit: impl AudioUnit32 + 'static) -> Box<dyn Effect> {
+        Box::new(Self {
+            unit: Box::new(unit),
+        })
+    }
+}
+
+impl Effect for FundspEffect {
+    fn init(&mut self, sample_rate: u32) {
+        self.unit.set_sample_rate(sample_rate as f64);
+        self.unit.allocate();
+    }
+
+    fn on_change_sample_rate(&mut self, sample_rate: u32) {
+        self.unit.set_sample_rate(sample_rate as f64);
+    }
+
+    fn process(
+        &mut self,
+        input: Frame,
+        _dt: f64,
+        _clock_info_provider: &ClockInfoProvider,
+        _modulator_value_provider: &ModulatorValueProvider,
+    ) -> Frame {
+        let mut output = [0.0; 2];
+        self.unit.tick(&[input.left, input.right], &mut output);
+        Frame {
+            left: output[0],
+            right: output[1],
+        }
+    }
+}
+
+/// Follows the louder channel, and turns both down together
+struct Compressor {
+    /// As an amplitude
+    threshold: f32,
+    ratio: f32,
+    attack: f32,
+    release: f32,
+    /// How far the envelope moves towards the level each sample
+    attack_glide: f32,
+    release_glide: f32,
+    envelope: f32,
+}
+
+impl Compressor {
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        let glide = |seconds: f32| 1.0 - (-1.0 / (seconds.max(1e-4) * sample_rate as f32)).exp();
+        self.attack_glide = glide(self.attack);
+        self.release_glide = glide(self.release);
+    }
+
+    fn gain(&mut self, level: f32) -> f32 {
+        let glide = if level > self.envelope {
+            self.attack_glide
+        } else {
+            self.release_glide
+        };
+        self.envelope += (level - self.envelope) * glide;
+        if self.envelope > self.threshold {
+            // Every `ratio` decibels above the threshold come out as one
+            (self.envelope / self.threshold).powf(self.ratio.recip() - 1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+impl Effect for Compressor {
+    fn init(&mut self, sample_rate: u32) {
+        self.set_sample_rate(sample_rate);
+    }
+
+    fn on_change_sample_rate(&mut self, sample_rate: u32) {
+        self.set_sample_rate(sample_rate);
+    }
+
+    fn process(
+        &mut self,
+        input: Frame,
+        _dt: f64,
+        _clock_info_provider: &ClockInfoProvider,
+        _modulator_value_provider: &ModulatorValueProvider,
+    ) -> Frame {
+        input * self.gain(input.left.abs().max(input.right.abs()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bus, BusEffect, Compressor, MixerError, MixerSettings};
+    use assert2::{check, let_assert};
+    use kira::clock::clock_info::MockClockInfoProviderBuilder;
+    use kira::dsp::Frame;
+    use kira::manager::backend::mock::MockBackend;
+    use kira::manager::{AudioManager, AudioManagerSettings};
+    use kira::modulator::value_provider::MockModulatorValueProviderBuilder;
+    use kira::track::effect::EffectBuilder;
+
+    const SAMPLE_RATE: u32 = 1000;
+
+    fn manager() -> AudioManager<MockBackend> {
+        AudioManager::new(AudioManagerSettings::default()).unwrap()
+    }
+
+    /// What `effect` makes of an impulse, over `frames` frames
+    fn impulse_response(effect: BusEffect, frames: usize) -> Vec<Frame> {
+        let (mut effect, ()) = effect.build();
+        effect.init(SAMPLE_RATE);
+        let clocks = MockClockInfoProviderBuilder::new(0).build();
+        let modulators = MockModulatorValueProviderBuilder::new(0).build();
+        let dt = 1.0 / SAMPLE_RATE as f64;
+        (0..frames)
+            .map(|frame| {
+                let input = if frame == 0 {
+                    Frame::from_mono(1.0)
+                } else {
+                    Frame::ZERO
+                };
+                effect.process(input, dt, &clocks, &modulators)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fundsp_effects() {
+        let delay = impulse_response(
+            BusEffect::Delay {
+                seconds: 0.01,
+                feedback: 0.5,
+                wet: 1.0,
+            },
+            100,
+        );
+        check!(delay.iter().all(|frame| frame.left.is_finite()));
+        // Only echoes are heard, the first a hundredth of a second in
+        check!(delay[..5].iter().all(|frame| frame.left == 0.0));
+        let first = delay.iter().position(|frame| frame.left != 0.0);
+        check!(first.is_some_and(|first| (10..=12).contains(&first)));
+        check!(delay.iter().all(|frame| frame.left == frame.right));
+
+        let reverb = impulse_response(
+            BusEffect::Reverb {
+                room_size: 20.0,
+                time: 2.0,
+                wet: 1.0,
+            },
+            2000,
+        );
+        check!(reverb.iter().all(|frame| frame.left.is_finite()));
+        check!(reverb[1000..].iter().any(|frame| frame.left != 0.0));
+
+        // Both can also be built into buses
+        let mixer = MixerSettings::new()
+            .with_bus(Bus::new("reverb").with_effect(BusEffect::Reverb {
+                room_size: 20.0,
+                time: 2.0,
+                wet: 0.5,
+            }))
+            .with_bus(Bus::new("echo").with_effect(BusEffect::Delay {
+                seconds: 0.25,
+                feedback: 0.3,
+                wet: 0.5,
+            }));
+        check!(mixer.build(&mut manager()).unwrap().len() == 2);
+    }
+
+    #[test]
+    fn test_compressor() {
+        // Follows the level straight away
+        let mut compressor = Compressor {
+            threshold: 0.5,
+            ratio: 4.0,
+            attack: 0.0,
+            release: 0.0,
+            attack_glide: 1.0,
+            release_glide: 1.0,
+            envelope: 0.0,
+        };
+        check!(compressor.gain(0.25) == 1.0);
+        // 12dB over the threshold comes out 3dB over
+        let level = 0.5 * 4.0f32;
+        let out = level * compressor.gain(level);
+        check!((out / 0.5 - 2.0f32.sqrt()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_builder() {
+        let mixer = MixerSettings::new()
+            .with_bus(Bus::new("reverb").with_effect(BusEffect::Reverb {
+                room_size: 20.0,
+                time: 2.0,
+                wet: 1.0,
+            }))
+            .with_bus(Bus::new("music").with_volume(0.6).with_send("reverb", 0.2))
+            .with_main_track_bus("music");
+        check!(mixer.buses.len() == 2);
+        check!(mixer.buses[1].sends[0].to == "reverb");
+        check!(mixer.main_track_bus.as_deref() == Some("music"));
+    }
+
+    #[test]
+    fn test_build() {
+        // Buses can play into ones listed after them
+        let mixer = MixerSettings::new()
+            .with_bus(Bus::new("music").with_volume(0.6).with_send("reverb", 0.2))
+            .with_bus(
+                Bus::new("sfx")
+                    .with_parent("music")
+                    .with_send("reverb", 0.4)
+                    .with_effect(BusEffect::Compressor {
+                        threshold: -12.0,
+                        ratio: 4.0,
+                        attack: 0.01,
+                        release: 0.1,
+                    }),
+            )
+            .with_bus(Bus::new("reverb").with_effect(BusEffect::Reverb {
+                room_size: 20.0,
+                time: 2.0,
+                wet: 1.0,
+            }));
+        let mut manager = manager();
+        let buses = mixer.build(&mut manager).unwrap();
+        check!(buses.len() == 3);
+        check!(["music", "sfx", "reverb"]
+            .iter()
+            .all(|name| buses.contains_key(*name)));
+        check!(manager.num_sub_tracks() == 3);
+    }
+
+    #[test]
+    fn test_build_cycle() {
+        let mixer = MixerSettings::new()
+            .with_bus(Bus::new("music").with_parent("sfx"))
+            .with_bus(Bus::new("sfx").with_send("music", 0.5));
+        let_assert!(Err(MixerError::Cycle(name)) = mixer.build(&mut manager()));
+        check!(name == "music");
+
+        let mixer = MixerSettings::new().with_bus(Bus::new("echo").with_send("echo", 0.5));
+        let_assert!(Err(MixerError::Cycle(name)) = mixer.build(&mut manager()));
+        check!(name == "echo");
+    }
+
+    #[test]
+    fn test_build_unknown_bus() {
+        let mixer = MixerSettings::new().with_bus(Bus::new("sfx").with_parent("music"));
+        let_assert!(Err(MixerError::UnknownBus(name)) = mixer.build(&mut manager()));
+        check!(name == "music");
+
+        let mixer = MixerSettings::new().with_bus(Bus::new("sfx").with_send("reverb", 0.4));
+        let_assert!(Err(MixerError::UnknownBus(name)) = mixer.build(&mut manager()));
+        check!(name == "reverb");
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_from_ron() {
+        let mixer = MixerSettings::from_ron(
+            r#"(
+                buses: [
+                    (name: "reverb", effects: [Reverb(room_size: 20.0, time: 2.0, wet: 1.0)]),
+                    (name: "music", volume: 0.6, sends: [(to: "reverb", level: 0.2)]),
+                    (name: "sfx", parent: Some("music"), effects: [Lowpass(cutoff: 2000.0, q: 0.7)]),
+                ],
+            )"#,
+        )
+        .unwrap();
+        check!(mixer.buses.len() == 3);
+        check!(mixer.buses[0].volume == 1.0);
+        check!(mixer.buses[2].parent.as_deref() == Some("music"));
+        check!(
+            mixer.buses[2].effects
+                == [BusEffect::Lowpass {
+                    cutoff: 2000.0,
+                    q: 0.7
+                }]
+        );
+    }
+}
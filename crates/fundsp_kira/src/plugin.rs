@@ -0,0 +1,838 @@
+//! Play [`Machine`]s on kira tracks from Bevy
+
+use crate::command::{self, FundspCommandError, FundspCommandQueue};
+use crate::dynamic::{self, DynamicTracks};
+use crate::mixer::MixerSettings;
+use crate::{
+    spatial, ChannelDetails, Machine, Machined, MachinedHandle, NoAudioOutputs, Trackable,
+};
+
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use fundsp::prelude::DEFAULT_SR;
+use generic_array::{
+    functional::FunctionalSequence as _, sequence::GenericSequence as _, typenum::U512,
+    ArrayLength, GenericArray,
+};
+use kira::{
+    manager::{
+        backend::{Backend, DefaultBackend},
+        error::{AddModulatorError, AddSubTrackError, PlaySoundError},
+        AudioManager, AudioManagerSettings, Capacities,
+    },
+    modulator::tweener::{TweenerBuilder, TweenerHandle},
+    track::{
+        effect::panning_control::{PanningControlBuilder, PanningControlHandle},
+        TrackBuilder, TrackHandle, TrackRoutes,
+    },
+    tween::Tween,
+    CommandError, OutputDestination, Volume,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Sets up the audio output, [`Machine`] assets, the [`MainTrack`] and [`DynamicTracks`]
+pub struct FundspAudioPlugin;
+
+/// Marks the [`MainTrack`], which plays up to 8 sounds at once into
+/// [`MixerSettings::main_track_bus`], or straight to kira's main track
+#[derive(Resource)]
+pub struct DefaultTrack;
+
+pub type DefaultBufferLength = U512;
+pub type MainTrack = Track<DefaultTrack, DefaultBufferLength>;
+
+impl Plugin for FundspAudioPlugin {
+    fn build(&self, app: &mut App) {
+        let main_track_bus = app
+            .world
+            .get_resource::<MixerSettings>()
+            .and_then(|mixer| mixer.main_track_bus.clone());
+        app.init_non_send_resource::<FundspAudioOutput>()
+            .init_resource::<FundspCommandQueue>()
+            .init_asset::<Machine>()
+            .add_event::<command::FundspCommandFailed>()
+            .add_track_with_settings::<DefaultTrack, DefaultBufferLength>(TrackSettings {
+                voices: 8,
+                bus: main_track_bus,
+                ..default()
+            })
+            .add_dynamic_tracks::<DefaultBufferLength>()
+            .add_systems(
+                PostUpdate,
+                command::apply_commands
+                    .in_set(FundspAudioSystemSet::ApplyCommands)
+                    .before(FundspAudioSystemSet::PlayTypedChannels),
+            )
+            .add_systems(
+                PostUpdate,
+                (spatial::spatialize_emitters, spatial::play_emitters)
+                    .chain()
+                    .in_set(FundspAudioSystemSet::Spatialize)
+                    .after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+pub trait FundspAudioApp {
+    fn add_track<T: Resource, N: ArrayLength>(
+        &mut self,
+        subchannel_id: Option<usize>,
+    ) -> &mut Self {
+        self.add_track_with_settings::<T, N>(TrackSettings {
+            subtrack_id: subchannel_id,
+            ..default()
+        })
+    }
+
+    fn add_track_with_sample_rate<T: Resource, N: ArrayLength>(
+        &mut self,
+        sample_rate: f64,
+        subtrack_id: Option<usize>,
+    ) -> &mut Self {
+        self.add_track_with_settings::<T, N>(TrackSettings {
+            sample_rate,
+            subtrack_id,
+            ..default()
+        })
+    }
+
+    fn add_track_with_settings<T: Resource, N: ArrayLength>(
+        &mut self,
+        settings: TrackSettings,
+    ) -> &mut Self;
+
+    /// Let [`DynamicTracks`](crate::DynamicTracks) keeping `N` samples be created at run-time
+    fn add_dynamic_tracks<N: ArrayLength>(&mut self) -> &mut Self;
+}
+
+/// How a [`Track`] is set up
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackSettings {
+    pub sample_rate: f64,
+    /// Play on the kira sub-track with this id, creating it if needed, instead of the main track
+    pub subtrack_id: Option<usize>,
+    /// The [`MixerSettings`] bus the track's sub-track plays into, instead of kira's main track
+    ///
+    /// Tracks sharing a sub-track play into the bus of the first of them.
+    pub bus: Option<String>,
+    /// How many sounds can play on the track at once
+    pub voices: usize,
+    /// What to do when that many are already playing
+    pub stealing: VoiceStealing,
+}
+
+impl Default for TrackSettings {
+    fn default() -> Self {
+        Self {
+            sample_rate: DEFAULT_SR,
+            subtrack_id: None,
+            bus: None,
+            voices: 1,
+            stealing: VoiceStealing::Oldest,
+        }
+    }
+}
+
+/// What a [`Track`] does when asked to play more sounds than it has voices for
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VoiceStealing {
+    /// Stop the sound that started first
+    #[default]
+    Oldest,
+    /// Stop the sound with the lowest RMS
+    Quietest,
+    /// Don't play the new sound
+    Refuse,
+}
+
+/// Labels for audio systems
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub enum FundspAudioSystemSet {
+    /// Label for systems in [`CoreStage::PreUpdate`] that clean up tracked audio instances
+    // InstanceCleanup,
+    /// Label for systems in [`PostUpdate`] that set up and play [`DynamicTracks`](crate::DynamicTracks)
+    PlayDynamicChannels,
+    /// Label for systems in [`CoreStage::PostUpdate`] that process audio commands for typed channels
+    PlayTypedChannels,
+    /// Label for the exclusive system in [`PostUpdate`] that applies queued [`FundspCommands`](crate::FundspCommands)
+    ApplyCommands,
+    /// Label for systems in [`PostUpdate`] that play and place [`AudioEmitter`](spatial::AudioEmitter)s
+    Spatialize,
+}
+
+impl FundspAudioApp for App {
+    fn add_dynamic_tracks<N: ArrayLength>(&mut self) -> &mut Self {
+        self.init_resource::<DynamicTracks<N>>().add_systems(
+            PostUpdate,
+            dynamic::update_dynamic_tracks::<N>.in_set(FundspAudioSystemSet::PlayDynamicChannels),
+        )
+    }
+
+    fn add_track_with_settings<T: Resource, N: ArrayLength>(
+        &mut self,
+        settings: TrackSettings,
+    ) -> &mut Self {
+        self.add_systems(
+            Startup,
+            move |mut commands: Commands, mut output: NonSendMut<FundspAudioOutput>| {
+                commands.insert_resource(output.new_track::<T, N>(&settings));
+            },
+        );
+        self.init_resource::<FundspCommandQueue>()
+            .world
+            .resource_mut::<FundspCommandQueue>()
+            .add_track::<T, N>();
+        #[cfg(feature = "tweening")]
+        self.add_systems(
+            PostUpdate,
+            crate::tweening::apply_faders::<T, N>.in_set(FundspAudioSystemSet::PlayTypedChannels),
+        );
+        self.add_systems(
+            PostUpdate,
+            load_next_machines::<T, N>.in_set(FundspAudioSystemSet::PlayTypedChannels),
+        )
+    }
+}
+
+fn load_next_machines<T: Resource, N: ArrayLength>(
+    mut output: NonSendMut<FundspAudioOutput>,
+    mut track: ResMut<Track<T, N>>,
+    machines: Res<Assets<Machine>>,
+    server: Option<Res<AssetServer>>,
+) {
+    output.play_next_machines(&mut track, &machines, server.as_deref());
+}
+
+/// Whether `machine` is still being loaded, so playing it should wait
+///
+/// Machines that failed to load, or that nothing is loading, never will be.
+pub(crate) fn machine_loading(server: Option<&AssetServer>, machine: &Handle<Machine>) -> bool {
+    server.is_some_and(|server| server.load_state(machine) == LoadState::Loading)
+}
+
+/// This resource is used to configure the audio backend at creation
+///
+/// It needs to be inserted before adding the [`FundspAudioPlugin`] and will be
+/// consumed by it. Settings cannot be changed at run-time!
+#[derive(Resource)]
+pub struct FundspBackendSettings {
+    /// The number of commands that can be sent to the audio backend at a time.
+    ///
+    /// Each action you take, like playing or pausing a sound
+    /// queues up one command.
+    ///
+    /// Note that configuring a channel will cause one command per sound in the channel!
+    command_capacity: usize,
+    /// The maximum number of sounds that can be playing at a time.
+    // TODO Check if this ends up being equal to the max number of channels
+    sound_capacity: usize,
+}
+
+impl Default for FundspBackendSettings {
+    fn default() -> Self {
+        Self {
+            command_capacity: 128,
+            sound_capacity: 128,
+        }
+    }
+}
+
+impl From<FundspBackendSettings> for AudioManagerSettings<DefaultBackend> {
+    fn from(settings: FundspBackendSettings) -> Self {
+        AudioManagerSettings {
+            capacities: Capacities {
+                command_capacity: settings.command_capacity,
+                sound_capacity: settings.sound_capacity,
+                ..default()
+            },
+            ..default()
+        }
+    }
+}
+
+pub struct FundspAudioOutput<B: Backend = DefaultBackend> {
+    manager: Option<AudioManager<B>>,
+    sub_channels: HashMap<usize, SharedSubTrack>,
+    /// Kept so the buses aren't removed
+    buses: HashMap<String, TrackHandle>,
+    /// [`MixerSettings::main_track_bus`]
+    main_track_bus: Option<String>,
+}
+
+/// A kira sub-track, with what [`Track`]s need to control it
+struct SubTrack {
+    handle: TrackHandle,
+    panning: PanningControlHandle,
+}
+
+/// Tracks with the same subtrack id play on the same sub-track
+type SharedSubTrack = Arc<Mutex<SubTrack>>;
+
+#[derive(thiserror::Error, Debug)]
+enum NewTrackError {
+    #[error(transparent)]
+    SubTrack(#[from] AddSubTrackError),
+    #[error(transparent)]
+    Modulator(#[from] AddModulatorError),
+}
+
+impl FromWorld for FundspAudioOutput {
+    fn from_world(world: &mut World) -> Self {
+        let settings = world
+            .remove_resource::<FundspBackendSettings>()
+            .unwrap_or_default();
+        let mut manager = AudioManager::new(settings.into());
+        if let Err(ref setup_err) = manager {
+            warn!("failed to setup up fundsp audio: {setup_err:?}")
+        }
+        let mixer = world.remove_resource::<MixerSettings>().unwrap_or_default();
+        let buses = match manager.as_mut().map(|manager| mixer.build(manager)) {
+            Ok(Ok(buses)) => buses,
+            Ok(Err(mixer_err)) => {
+                warn!("failed to set up mixer: {mixer_err}");
+                HashMap::new()
+            }
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            manager: manager.ok(),
+            sub_channels: HashMap::new(),
+            buses,
+            main_track_bus: mixer.main_track_bus,
+        }
+    }
+}
+
+impl FundspAudioOutput {
+    /// Where to play into `bus`, or the main track's bus without one
+    pub(crate) fn bus_destination(&self, bus: Option<&str>) -> OutputDestination {
+        let Some(bus) = bus.or(self.main_track_bus.as_deref()) else {
+            return OutputDestination::MAIN_TRACK;
+        };
+        match self.buses.get(bus) {
+            Some(handle) => OutputDestination::Track(handle.id()),
+            None => {
+                warn!("No bus named {bus:?}, routing to main");
+                OutputDestination::MAIN_TRACK
+            }
+        }
+    }
+
+    /// A track playing on the sub-track `settings` asks for, or one of its own
+    ///
+    /// Without audio, or if the sub-track can't be made, the track has nothing to control
+    /// and plays to kira's main track.
+    fn new_track<T, N: ArrayLength>(&mut self, settings: &TrackSettings) -> Track<T, N> {
+        let mut track = Track::new(settings, kira::OutputDestination::MAIN_TRACK);
+        self.attach(&mut track, settings);
+        track
+    }
+
+    /// Move `track` onto the sub-track `settings` asks for, keeping its volume, panning and rate
+    pub(crate) fn attach<T, N: ArrayLength>(
+        &mut self,
+        track: &mut Track<T, N>,
+        settings: &TrackSettings,
+    ) {
+        let Some(manager) = self.manager.as_mut() else {
+            debug!("No manager, so tracks can't be controlled");
+            return;
+        };
+        match Self::add_controls(manager, &mut self.sub_channels, &self.buses, settings) {
+            Ok((sub_track, rate)) => {
+                let id = sub_track
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .handle
+                    .id();
+                track.output = kira::OutputDestination::Track(id);
+                track.sub_track = Some(sub_track);
+                track.rate_tweener = Some(rate);
+                if let Err(e) = track.reapply_controls() {
+                    warn!("Failed to control subtrack: {e}");
+                }
+            }
+            Err(e) => warn!("Failed to create subtrack: {e}, routing to main"),
+        }
+    }
+
+    fn add_controls(
+        manager: &mut AudioManager,
+        sub_channels: &mut HashMap<usize, SharedSubTrack>,
+        buses: &HashMap<String, TrackHandle>,
+        settings: &TrackSettings,
+    ) -> Result<(SharedSubTrack, TweenerHandle), NewTrackError> {
+        let subtrack_id = settings.subtrack_id;
+        let rate = manager.add_modulator(TweenerBuilder { initial_value: 1.0 })?;
+        if let Some(sub_track) = subtrack_id.and_then(|id| sub_channels.get(&id)) {
+            return Ok((sub_track.clone(), rate));
+        }
+        let routes = match settings.bus.as_ref().map(|bus| (bus, buses.get(bus))) {
+            Some((_, Some(bus))) => TrackRoutes::parent(bus.id()),
+            Some((bus, None)) => {
+                warn!("No bus named {bus:?}, routing to main");
+                TrackRoutes::new()
+            }
+            None => TrackRoutes::new(),
+        };
+        let mut builder = TrackBuilder::new().routes(routes);
+        let panning = builder.add_effect(PanningControlBuilder::default());
+        let sub_track = Arc::new(Mutex::new(SubTrack {
+            handle: manager.add_sub_track(builder)?,
+            panning,
+        }));
+        if let Some(subtrack_id) = subtrack_id {
+            debug!("Created new subtrack for subtrack id {subtrack_id}");
+            sub_channels.insert(subtrack_id, sub_track.clone());
+        }
+        Ok((sub_track, rate))
+    }
+
+    /// Play the loaded machines [`Track::play`] was asked for
+    ///
+    /// The rest wait while their machine is [loading](machine_loading).
+    pub(crate) fn play_next_machines<T: Send + 'static, N: ArrayLength>(
+        &mut self,
+        track: &mut Track<T, N>,
+        machines: &Assets<Machine>,
+        server: Option<&AssetServer>,
+    ) {
+        for (machine, handle) in std::mem::take(&mut track.next_machines) {
+            let Some(loaded) = machines.get(&machine) else {
+                if machine_loading(server, &machine) {
+                    track.next_machines.push((machine, handle));
+                } else {
+                    warn!("{}", FundspCommandError::MachineNotLoaded);
+                    handle.never_played();
+                }
+                continue;
+            };
+            if let Err(err) = self.play(loaded, track, handle) {
+                warn!("{err}");
+            }
+        }
+    }
+
+    /// Play `machine` on a new voice of `track`, stealing one if they are all playing
+    ///
+    /// The voice is controlled by `handle`, which is finished if it can't be played.
+    /// Systems that can't take this non-`Send` resource can use [`FundspCommands`](crate::FundspCommands).
+    pub(crate) fn play<T: Send + 'static, N: ArrayLength>(
+        &mut self,
+        machine: &Machine,
+        track: &mut Track<T, N>,
+        handle: MachinedHandle,
+    ) -> Result<(), FundspCommandError> {
+        if self.manager.is_none() {
+            handle.never_played();
+            return Ok(());
+        }
+        track.voices.retain(|voice| !voice.handle.is_finished());
+        if track.voices.len() >= track.max_voices {
+            let Some(stolen) = track.voice_to_steal() else {
+                handle.never_played();
+                return Err(FundspCommandError::NoFreeVoice(track.max_voices));
+            };
+            track.voices.remove(stolen).handle.unload();
+        }
+        let taps = Trackable::default();
+        let mut machined = Machined::<T, N>::new(
+            machine,
+            track.output,
+            track.sample_rate,
+            Trackable::clone(&taps),
+            handle.clone(),
+        );
+        machined.settings.rate = track.rate_tweener.as_ref().map(TweenerHandle::id);
+        match self.play_machined(machined) {
+            Ok(Some(handle)) => track.voices.push(Voice { handle, taps }),
+            Ok(None) => handle.never_played(),
+            Err(err) => {
+                handle.never_played();
+                return Err(err.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Play `machined` if we have somewhere to play it
+    pub(crate) fn play_machined<T: Send + 'static, N: ArrayLength>(
+        &mut self,
+        machined: Machined<T, N>,
+    ) -> Result<Option<MachinedHandle>, PlaySoundError<NoAudioOutputs>> {
+        let Some(manager) = self.manager.as_mut() else {
+            return Ok(None);
+        };
+        manager.play(machined).map(Some)
+    }
+}
+
+/// One sound playing on a [`Track`]
+pub(crate) struct Voice<T, N: ArrayLength> {
+    pub(crate) handle: MachinedHandle,
+    /// What this voice sounds like, mixed with the others by the track
+    pub(crate) taps: Trackable<T, N>,
+}
+
+/// Plays [`Machine`]s on a number of voices, and keeps track of what they sound like together
+///
+/// `T` tells tracks apart, and `N` is how many recent samples are kept.
+///
+/// Volume, panning and playback rate can be changed over a kira [`Tween`]. Tracks with
+/// the same subtrack id share their volume and panning.
+#[derive(Resource)]
+pub struct Track<T, N: ArrayLength> {
+    output: kira::OutputDestination,
+    sample_rate: f64,
+    sub_track: Option<SharedSubTrack>,
+    /// Read by every voice, to speed up or slow down
+    rate_tweener: Option<TweenerHandle>,
+    volume: f32,
+    panning: f32,
+    rate: f32,
+    max_voices: usize,
+    stealing: VoiceStealing,
+    /// Oldest first
+    pub(crate) voices: Vec<Voice<T, N>>,
+    /// Machines to play once loaded, with the handles they'll be controlled by
+    next_machines: Vec<(Handle<Machine>, MachinedHandle)>,
+}
+
+impl<T, N: ArrayLength> Default for Track<T, N> {
+    fn default() -> Self {
+        Self::new(
+            &TrackSettings::default(),
+            kira::OutputDestination::MAIN_TRACK,
+        )
+    }
+}
+
+impl<T, N: ArrayLength> Track<T, N> {
+    pub(crate) fn new(settings: &TrackSettings, output: kira::OutputDestination) -> Self {
+        Self {
+            output,
+            sample_rate: settings.sample_rate,
+            sub_track: None,
+            rate_tweener: None,
+            volume: 1.0,
+            panning: 0.0,
+            rate: 1.0,
+            max_voices: settings.voices.max(1),
+            stealing: settings.stealing,
+            voices: Vec::new(),
+            next_machines: Vec::new(),
+        }
+    }
+
+    /// Get this track's sample rate
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Get the most recent sample values, of all voices together
+    pub fn samples(&self) -> GenericArray<(f32, f32), N> {
+        self.mix(|channel| channel.samples())
+    }
+
+    /// Get the most recent RMS values, of all voices together
+    pub fn rms(&self) -> GenericArray<(f32, f32), N> {
+        // Unrelated sounds add up in power, rather than amplitude
+        self.mix(|channel| channel.rms().map(|rms| rms * rms))
+            .map(|(left, right)| (left.sqrt(), right.sqrt()))
+    }
+
+    /// Sum what `tap` gets from each playing voice
+    fn mix(
+        &self,
+        tap: impl Fn(&ChannelDetails<N>) -> GenericArray<f32, N>,
+    ) -> GenericArray<(f32, f32), N> {
+        self.voices
+            .iter()
+            .filter(|voice| !voice.handle.is_finished())
+            .fold(GenericArray::generate(|_| (0.0, 0.0)), |mix, voice| {
+                let left = tap(&voice.taps.left_channel);
+                let right = tap(&voice.taps.right_channel);
+                mix.zip(left.zip(right, |l, r| (l, r)), |(mix_l, mix_r), (l, r)| {
+                    (mix_l + l, mix_r + r)
+                })
+            })
+    }
+
+    /// Play `machine` on a new voice once it is loaded
+    ///
+    /// The handle controls just this voice, and can set its parameters before it starts.
+    pub fn play(&mut self, machine: Handle<Machine>) -> MachinedHandle {
+        let handle = MachinedHandle::default();
+        self.next_machines.push((machine, handle.clone()));
+        handle
+    }
+
+    /// The index of the voice to make room with, if the track steals voices
+    fn voice_to_steal(&self) -> Option<usize> {
+        match self.stealing {
+            VoiceStealing::Oldest => (!self.voices.is_empty()).then_some(0),
+            VoiceStealing::Quietest => self
+                .voices
+                .iter()
+                .map(|voice| {
+                    let left = voice.taps.left_channel.latest_rms();
+                    let right = voice.taps.right_channel.latest_rms();
+                    left * left + right * right
+                })
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(index, _)| index),
+            VoiceStealing::Refuse => None,
+        }
+    }
+
+    /// How many voices are playing
+    pub fn playing(&self) -> usize {
+        self.voices
+            .iter()
+            .filter(|voice| !voice.handle.is_finished())
+            .count()
+    }
+
+    pub fn max_voices(&self) -> usize {
+        self.max_voices
+    }
+
+    /// Stop every voice
+    pub fn stop(&mut self) {
+        for voice in self.voices.drain(..) {
+            voice.handle.unload();
+        }
+    }
+
+    pub fn pause(&self) {
+        for voice in &self.voices {
+            voice.handle.pause();
+        }
+    }
+
+    pub fn resume(&self) {
+        for voice in &self.voices {
+            voice.handle.resume();
+        }
+    }
+
+    /// Set parameter `name` on every voice, returning whether they all have one
+    ///
+    /// Voices waiting for their machine to load start with the value.
+    pub fn set_param(&self, name: &str, value: f32) -> bool {
+        let waiting = self.next_machines.iter().map(|(_, handle)| handle);
+        let mut known = true;
+        // Every voice gets the value, even after one without the parameter
+        for handle in self.voices.iter().map(|voice| &voice.handle).chain(waiting) {
+            known &= handle.set_param(name, value);
+        }
+        known
+    }
+
+    /// Scale what this track plays, within a few milliseconds
+    pub fn set_volume(&mut self, volume: f32) -> Result<(), CommandError> {
+        self.tween_volume(volume, Tween::default())
+    }
+
+    /// Fade what this track plays to `volume`, as an amplitude
+    pub fn tween_volume(&mut self, volume: f32, tween: Tween) -> Result<(), CommandError> {
+        self.volume = volume;
+        match &self.sub_track {
+            Some(sub_track) => sub_track
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .handle
+                .set_volume(Volume::Amplitude(volume as f64), tween),
+            None => Ok(()),
+        }
+    }
+
+    /// The volume the track is at, or fading to
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Pan what this track plays, from -1.0 for fully left to 1.0 for fully right
+    pub fn set_panning(&mut self, panning: f32) -> Result<(), CommandError> {
+        self.tween_panning(panning, Tween::default())
+    }
+
+    pub fn tween_panning(&mut self, panning: f32, tween: Tween) -> Result<(), CommandError> {
+        self.panning = panning.clamp(-1.0, 1.0);
+        match &self.sub_track {
+            // kira pans from 0.0 to 1.0
+            Some(sub_track) => sub_track
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .panning
+                .set_panning((self.panning as f64 + 1.0) / 2.0, tween),
+            None => Ok(()),
+        }
+    }
+
+    pub fn panning(&self) -> f32 {
+        self.panning
+    }
+
+    /// Speed up or slow down what this track plays, which also changes its pitch
+    pub fn set_rate(&mut self, rate: f32) -> Result<(), CommandError> {
+        self.tween_rate(rate, Tween::default())
+    }
+
+    pub fn tween_rate(&mut self, rate: f32, tween: Tween) -> Result<(), CommandError> {
+        self.rate = rate.max(0.0);
+        match &mut self.rate_tweener {
+            Some(tweener) => tweener.set(self.rate as f64, tween),
+            None => Ok(()),
+        }
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    /// Send the volume, panning and rate that were set before the track had a sub-track
+    ///
+    /// Ones left alone aren't sent, so as not to undo what other tracks on a shared
+    /// sub-track set.
+    fn reapply_controls(&mut self) -> Result<(), CommandError> {
+        if self.volume != 1.0 {
+            self.set_volume(self.volume)?;
+        }
+        if self.panning != 0.0 {
+            self.set_panning(self.panning)?;
+        }
+        if self.rate != 1.0 {
+            self.set_rate(self.rate)?;
+        }
+        Ok(())
+    }
+
+    pub fn buffer_length(&self) -> usize {
+        N::to_usize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FundspAudioOutput, Track, TrackSettings, Voice, VoiceStealing};
+    use crate::{Machine, MachinedHandle, Trackable};
+    use assert2::check;
+    use bevy::asset::LoadState;
+    use bevy::prelude::*;
+    use generic_array::typenum::U4;
+    use kira::manager::backend::mock::MockBackend;
+    use kira::manager::AudioManager;
+    use kira::track::TrackBuilder;
+    use kira::OutputDestination;
+    use std::collections::HashMap;
+    use std::sync::atomic::Ordering;
+
+    const EPSILON: f32 = 1e-6;
+
+    /// A voice that has been playing at `level`
+    fn voice(level: f32) -> Voice<(), U4> {
+        let voice = Voice {
+            handle: MachinedHandle::default(),
+            taps: Trackable::default(),
+        };
+        for channel in [&voice.taps.left_channel, &voice.taps.right_channel] {
+            channel.store_sample(level);
+            channel.store_rms(level);
+        }
+        voice
+    }
+
+    #[test]
+    fn test_voice_stealing() {
+        let track = |stealing| {
+            let settings = TrackSettings {
+                voices: 3,
+                stealing,
+                ..default()
+            };
+            let mut track = Track::<(), U4>::new(&settings, kira::OutputDestination::MAIN_TRACK);
+            track.voices = vec![voice(0.3), voice(0.1), voice(0.2)];
+            track
+        };
+        check!(track(VoiceStealing::Oldest).voice_to_steal() == Some(0));
+        check!(track(VoiceStealing::Quietest).voice_to_steal() == Some(1));
+        check!(track(VoiceStealing::Refuse).voice_to_steal() == None);
+    }
+
+    #[test]
+    fn test_mixed_taps() {
+        let track = Track::<(), U4> {
+            voices: vec![voice(0.3), voice(0.4)],
+            ..Default::default()
+        };
+        let (left, right) = track.samples()[3];
+        check!((left - 0.7).abs() < EPSILON);
+        check!((right - 0.7).abs() < EPSILON);
+        let (left, _) = track.rms()[3];
+        check!((left - 0.5).abs() < EPSILON);
+        check!(track.samples()[0] == (0.0, 0.0));
+
+        // Finished voices aren't heard
+        track.voices[1]
+            .handle
+            .finished
+            .store(true, Ordering::Release);
+        check!(track.playing() == 1);
+        check!((track.samples()[3].0 - 0.3).abs() < EPSILON);
+    }
+
+    /// An output with nowhere to play
+    fn output() -> FundspAudioOutput {
+        FundspAudioOutput {
+            manager: None,
+            sub_channels: HashMap::new(),
+            buses: HashMap::new(),
+            main_track_bus: None,
+        }
+    }
+
+    #[test]
+    fn test_bus_destination() {
+        let mut manager = AudioManager::<MockBackend>::new(default()).unwrap();
+        let music = manager.add_sub_track(TrackBuilder::new()).unwrap();
+        let music_id = music.id();
+        let mut output = output();
+        check!(output.bus_destination(None) == OutputDestination::MAIN_TRACK);
+        output.buses.insert("music".to_owned(), music);
+        check!(output.bus_destination(Some("music")) == OutputDestination::Track(music_id));
+        check!(output.bus_destination(Some("sfx")) == OutputDestination::MAIN_TRACK);
+
+        // Without a bus, sounds go where the main track does
+        output.main_track_bus = Some("music".to_owned());
+        check!(output.bus_destination(None) == OutputDestination::Track(music_id));
+    }
+
+    #[test]
+    fn test_next_machines_fail_without_machine() {
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()));
+        let server = app.world.resource::<AssetServer>();
+        let machine = Handle::<Machine>::weak_from_u128(7);
+        check!(server.load_state(&machine) == LoadState::NotLoaded);
+
+        // Nothing is loading the machine, so it isn't waited for
+        let mut output = output();
+        let mut track = Track::<(), U4>::default();
+        let handle = track.play(machine.clone());
+        output.play_next_machines(&mut track, &Assets::<Machine>::default(), Some(server));
+        check!(track.next_machines.is_empty());
+        check!(handle.is_finished());
+        check!(!handle.is_started());
+
+        // Nor without an asset server at all
+        let handle = track.play(machine);
+        output.play_next_machines(&mut track, &Assets::<Machine>::default(), None);
+        check!(track.next_machines.is_empty());
+        check!(handle.is_finished());
+    }
+}
@@ -1,5 +1,8 @@
 //! emulation of sfxr using fundsp
-use crate::fundsp_kira::Machine;
+//!
+//! With the `bevy` feature, [`SfxrPlugin`] loads `.sfxr.ron` files as [`Machine`]s.
+use crate::Machine;
+#[cfg(feature = "bevy")]
 use bevy::{
     asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
     prelude::*,
@@ -7,8 +10,10 @@ use bevy::{
 };
 use fundsp::hacker32::*;
 
+#[cfg(feature = "bevy")]
 pub struct SfxrPlugin;
 
+#[cfg(feature = "bevy")]
 impl Plugin for SfxrPlugin {
     fn build(&self, app: &mut App) {
         app.init_asset_loader::<SfxrLoader>();
@@ -48,9 +53,11 @@ impl From<Sfxr> for Machine {
     }
 }
 
+#[cfg(feature = "bevy")]
 #[derive(Default)]
 struct SfxrLoader;
 
+#[cfg(feature = "bevy")]
 #[non_exhaustive]
 #[derive(thiserror::Error, Debug)]
 enum SfxrLoaderError {
@@ -62,6 +69,7 @@ enum SfxrLoaderError {
     RonSpannedError(#[from] ron::error::SpannedError),
 }
 
+#[cfg(feature = "bevy")]
 impl AssetLoader for SfxrLoader {
     type Asset = Machine;
     type Settings = ();
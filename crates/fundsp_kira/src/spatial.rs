@@ -0,0 +1,358 @@
+//! Positional audio
+//!
+//! An [`AudioEmitter`] plays a [`Machine`] from its entity, and is heard by the
+//! [`AudioListener`]. Every frame, emitters are panned with equal power by how far
+//! left or right of the listener they are, and quieted with distance as their
+//! [`Attenuation`] says. The maths lives in [`AudioListener::hear`], so it can be
+//! checked without an audio device.
+
+use crate::{FundspAudioOutput, Machine, Machined, MachinedHandle};
+
+use bevy::prelude::*;
+use fundsp::prelude::DEFAULT_SR;
+use generic_array::typenum::U1;
+use std::f32::consts::FRAC_PI_4;
+
+/// Hears [`AudioEmitter`]s, usually from the camera
+///
+/// Only one listener is heard with. Without one, emitters play centered and at full volume.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct AudioListener {
+    /// Ignore how far in front of or behind the listener emitters are
+    ///
+    /// 2D cameras sit far in front of everything they see, which would otherwise
+    /// make every emitter sound distant and centered.
+    pub flat: bool,
+}
+
+impl AudioListener {
+    /// A listener for 2D games, which ignores depth
+    pub fn flat() -> Self {
+        Self { flat: true }
+    }
+
+    /// How an emitter at `emitter` sounds to this listener at `transform`
+    ///
+    /// Directions and distances are measured in the listener's space.
+    pub fn hear(
+        &self,
+        transform: &GlobalTransform,
+        emitter: Vec3,
+        attenuation: Attenuation,
+    ) -> Spatialization {
+        let mut local = transform.affine().inverse().transform_point3(emitter);
+        if self.flat {
+            local.z = 0.0;
+        }
+        let distance = local.length();
+        let pan = if distance > f32::EPSILON {
+            (local.x / distance).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        Spatialization {
+            pan,
+            gain: attenuation.gain(distance),
+        }
+    }
+}
+
+/// How emitters get quieter with distance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Attenuation {
+    /// Full volume at any distance
+    None,
+    /// Full volume up to `min_distance`, fading out linearly until `max_distance`
+    Linear {
+        min_distance: f32,
+        max_distance: f32,
+    },
+    /// Full volume up to `reference_distance`, then falling off inversely with distance
+    ///
+    /// Higher `rolloff` falls off faster, at 1.0 doubling the distance halves the gain.
+    Inverse {
+        reference_distance: f32,
+        rolloff: f32,
+    },
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self::Inverse {
+            reference_distance: 100.0,
+            rolloff: 1.0,
+        }
+    }
+}
+
+impl Attenuation {
+    /// The gain of an emitter `distance` away
+    pub fn gain(&self, distance: f32) -> f32 {
+        match *self {
+            Self::None => 1.0,
+            Self::Linear {
+                min_distance,
+                max_distance,
+            } => {
+                if distance <= min_distance {
+                    1.0
+                } else if distance >= max_distance {
+                    0.0
+                } else {
+                    1.0 - (distance - min_distance) / (max_distance - min_distance)
+                }
+            }
+            Self::Inverse {
+                reference_distance,
+                rolloff,
+            } => {
+                let reference_distance = reference_distance.max(f32::EPSILON);
+                let beyond = (distance - reference_distance).max(0.0);
+                reference_distance / (reference_distance + rolloff.max(0.0) * beyond)
+            }
+        }
+    }
+}
+
+/// Where an emitter is heard from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spatialization {
+    /// From -1.0 for fully left to 1.0 for fully right
+    pub pan: f32,
+    pub gain: f32,
+}
+
+impl Spatialization {
+    /// In front of the listener, at full volume
+    pub const CENTERED: Self = Self {
+        pan: 0.0,
+        gain: 1.0,
+    };
+
+    /// Gains for the left and right channels
+    pub fn stereo_gains(&self) -> (f32, f32) {
+        let (left, right) = equal_power_pan(self.pan);
+        (left * self.gain, right * self.gain)
+    }
+}
+
+impl Default for Spatialization {
+    fn default() -> Self {
+        Self::CENTERED
+    }
+}
+
+/// Left and right gains for `pan`, from -1.0 for fully left to 1.0 for fully right
+///
+/// The power of both channels adds up to the same wherever the sound is panned.
+pub fn equal_power_pan(pan: f32) -> (f32, f32) {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+    (angle.cos(), angle.sin())
+}
+
+/// Plays a [`Machine`] from its entity's [`GlobalTransform`]
+///
+/// The sound stops when the emitter is removed or despawned.
+#[derive(Component)]
+pub struct AudioEmitter {
+    pub attenuation: Attenuation,
+    /// The [bus](crate::mixer::Bus) to play into, instead of
+    /// [`MixerSettings::main_track_bus`](crate::mixer::MixerSettings::main_track_bus)
+    pub bus: Option<String>,
+    machine: Handle<Machine>,
+    /// Set until the machine is played
+    pending: bool,
+    playing: Option<MachinedHandle>,
+    spatialization: Spatialization,
+}
+
+impl AudioEmitter {
+    /// Play `machine` once it is loaded
+    pub fn new(machine: Handle<Machine>) -> Self {
+        Self {
+            attenuation: Attenuation::default(),
+            bus: None,
+            machine,
+            pending: true,
+            playing: None,
+            spatialization: Spatialization::CENTERED,
+        }
+    }
+
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    /// Play into `bus` instead of where the [`MainTrack`](crate::MainTrack) plays
+    pub fn with_bus(mut self, bus: impl Into<String>) -> Self {
+        self.bus = Some(bus.into());
+        self
+    }
+
+    /// Stop what is playing, and play `machine` instead
+    pub fn play(&mut self, machine: Handle<Machine>) {
+        self.machine = machine;
+        self.pending = true;
+    }
+
+    /// Play the machine again from the start
+    pub fn replay(&mut self) {
+        self.pending = true;
+    }
+
+    pub fn machine(&self) -> &Handle<Machine> {
+        &self.machine
+    }
+
+    /// What is playing, to set its parameters, once the machine has loaded
+    pub fn playing(&self) -> Option<&MachinedHandle> {
+        self.playing.as_ref()
+    }
+
+    /// How the emitter was last heard
+    pub fn spatialization(&self) -> Spatialization {
+        self.spatialization
+    }
+}
+
+impl Drop for AudioEmitter {
+    fn drop(&mut self) {
+        if let Some(playing) = self.playing.take() {
+            playing.unload();
+        }
+    }
+}
+
+pub(crate) fn play_emitters(
+    mut output: NonSendMut<FundspAudioOutput>,
+    machines: Res<Assets<Machine>>,
+    mut emitters: Query<&mut AudioEmitter>,
+) {
+    for mut emitter in emitters.iter_mut() {
+        if !emitter.pending {
+            continue;
+        }
+        let Some(machine) = machines.get(&emitter.machine) else {
+            continue;
+        };
+        if let Some(playing) = emitter.playing.take() {
+            playing.unload();
+        }
+        let machined = Machined::<AudioEmitter, U1>::from_machine(
+            machine,
+            output.bus_destination(emitter.bus.as_deref()),
+            DEFAULT_SR,
+        );
+        // Start where the emitter was last heard, rather than gliding in from the center
+        machined
+            .handle
+            .set_gains(emitter.spatialization.stereo_gains());
+        emitter.playing = output.play_machined(machined).unwrap_or_else(|err| {
+            error!("{err}");
+            None
+        });
+        emitter.pending = false;
+    }
+}
+
+pub(crate) fn spatialize_emitters(
+    listeners: Query<(&AudioListener, &GlobalTransform)>,
+    mut emitters: Query<(&mut AudioEmitter, &GlobalTransform)>,
+) {
+    let listener = listeners.iter().next();
+    for (mut emitter, transform) in emitters.iter_mut() {
+        let spatialization = match listener {
+            Some((listener, listener_transform)) => listener.hear(
+                listener_transform,
+                transform.translation(),
+                emitter.attenuation,
+            ),
+            None => Spatialization::CENTERED,
+        };
+        // Don't trip change detection every frame
+        let emitter = emitter.bypass_change_detection();
+        emitter.spatialization = spatialization;
+        if let Some(playing) = emitter.playing.as_ref() {
+            playing.set_gains(spatialization.stereo_gains());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{equal_power_pan, Attenuation, AudioListener, Spatialization};
+    use assert2::check;
+    use bevy::prelude::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    #[test]
+    fn test_equal_power_pan() {
+        let (left, right) = equal_power_pan(0.0);
+        check!((left - right).abs() < EPSILON);
+        check!((left - std::f32::consts::FRAC_1_SQRT_2).abs() < EPSILON);
+        check!(equal_power_pan(-1.0).1.abs() < EPSILON);
+        check!(equal_power_pan(1.0).0.abs() < EPSILON);
+        for pan in [-1.0, -0.7, -0.2, 0.3, 0.9, 2.0] {
+            let (left, right) = equal_power_pan(pan);
+            check!((left * left + right * right - 1.0).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_attenuation() {
+        check!(Attenuation::None.gain(1e6) == 1.0);
+
+        let linear = Attenuation::Linear {
+            min_distance: 10.0,
+            max_distance: 20.0,
+        };
+        check!(linear.gain(5.0) == 1.0);
+        check!((linear.gain(15.0) - 0.5).abs() < EPSILON);
+        check!(linear.gain(25.0) == 0.0);
+
+        let inverse = Attenuation::Inverse {
+            reference_distance: 10.0,
+            rolloff: 1.0,
+        };
+        check!(inverse.gain(0.0) == 1.0);
+        check!((inverse.gain(20.0) - 0.5).abs() < EPSILON);
+        check!((inverse.gain(40.0) - 0.25).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_hear() {
+        let listener = AudioListener::default();
+        let at_origin = GlobalTransform::IDENTITY;
+        let right = listener.hear(&at_origin, Vec3::X * 10.0, Attenuation::None);
+        check!(right.pan == 1.0);
+        let (left_gain, right_gain) = right.stereo_gains();
+        check!(left_gain.abs() < EPSILON && (right_gain - 1.0).abs() < EPSILON);
+        let left = listener.hear(&at_origin, Vec3::NEG_X * 10.0, Attenuation::None);
+        check!(left.pan == -1.0);
+        check!(
+            listener.hear(&at_origin, Vec3::ZERO, Attenuation::None) == Spatialization::CENTERED
+        );
+
+        // Turned around, right is left
+        let turned = GlobalTransform::from(Transform::from_rotation(Quat::from_rotation_y(
+            std::f32::consts::PI,
+        )));
+        let heard = listener.hear(&turned, Vec3::X * 10.0, Attenuation::None);
+        check!((heard.pan + 1.0).abs() < EPSILON);
+
+        let attenuation = Attenuation::Inverse {
+            reference_distance: 10.0,
+            rolloff: 1.0,
+        };
+        let camera = GlobalTransform::from_xyz(0.0, 0.0, 1000.0);
+        let deep = listener.hear(&camera, Vec3::X * 10.0, attenuation);
+        check!(deep.pan.abs() < 0.1);
+        check!(deep.gain < 0.1);
+        // Flat listeners only hear how far along x and y emitters are
+        let flat = AudioListener::flat().hear(&camera, Vec3::X * 20.0, attenuation);
+        check!(flat.pan == 1.0);
+        check!((flat.gain - 0.5).abs() < EPSILON);
+    }
+}
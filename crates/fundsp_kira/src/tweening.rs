@@ -0,0 +1,62 @@
+//! Fade tracks with bevy_tweening
+//!
+//! bevy_tweening animates components, so a [`TrackFader`] stands in for the track
+//! marked by `T`, and sets its volume whenever it changes.
+//!
+//! ```ignore
+//! commands.spawn((
+//!     TrackFader::<Music>::new(1.0),
+//!     Animator::new(Tween::new(
+//!         EaseFunction::QuadraticOut,
+//!         Duration::from_secs(2),
+//!         TrackVolumeLens { start: 1.0, end: 0.0 },
+//!     )),
+//! ));
+//! ```
+
+use crate::Track;
+
+use bevy::prelude::*;
+use bevy_tweening::Lens;
+use generic_array::ArrayLength;
+use std::marker::PhantomData;
+
+/// The volume of the track marked by `T`, for tweens to animate
+#[derive(Component)]
+pub struct TrackFader<T> {
+    pub volume: f32,
+    _marker: PhantomData<T>,
+}
+
+impl<T> TrackFader<T> {
+    pub fn new(volume: f32) -> Self {
+        Self {
+            volume,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Fades a [`TrackFader`] from `start` to `end`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackVolumeLens {
+    pub start: f32,
+    pub end: f32,
+}
+
+impl<T: Send + Sync + 'static> Lens<TrackFader<T>> for TrackVolumeLens {
+    fn lerp(&mut self, target: &mut TrackFader<T>, ratio: f32) {
+        target.volume = self.start + (self.end - self.start) * ratio;
+    }
+}
+
+pub(crate) fn apply_faders<T: Resource, N: ArrayLength>(
+    faders: Query<&TrackFader<T>, Changed<TrackFader<T>>>,
+    mut track: ResMut<Track<T, N>>,
+) {
+    for fader in faders.iter() {
+        if let Err(err) = track.set_volume(fader.volume) {
+            warn!("Failed to fade track: {err}");
+        }
+    }
+}
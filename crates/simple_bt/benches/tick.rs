@@ -0,0 +1,74 @@
+//! How fast trees think
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use simple_bt::composite::{Parallel, Selector, Sequence};
+use simple_bt::inspect::NodeStatus;
+use simple_bt::leaf::{Action, Condition};
+use simple_bt::{BehaviorArc, BehaviorNode, BehaviorRunner};
+
+/// Counts down every tick, running until it hits zero
+fn countdown() -> BehaviorArc<u64> {
+    Action::from_fn(|count: &mut u64| {
+        *count = count.saturating_sub(1);
+        if *count == 0 {
+            NodeStatus::Success
+        } else {
+            NodeStatus::Running
+        }
+    })
+    .arc()
+}
+
+/// A tree `depth` levels deep, with `width` children at every level
+///
+/// Levels alternate between selectors and sequences, so every tick takes a different path.
+fn wide_tree(depth: usize, width: usize, select: bool) -> BehaviorArc<u64> {
+    if depth == 0 {
+        return Condition::from_fn(|count: &u64| *count % 4 < 2).arc();
+    }
+    let children = (0..width).map(|_| wide_tree(depth - 1, width, !select));
+    if select {
+        children.collect::<Selector<_>>().arc()
+    } else {
+        children.collect::<Sequence<_>>().arc()
+    }
+}
+
+fn bench_tick(c: &mut Criterion) {
+    let tree = wide_tree(4, 4, true);
+    c.bench_function("tick wide tree", |b| {
+        let mut runner = BehaviorRunner::new(tree.clone());
+        let mut count = 0u64;
+        b.iter(|| {
+            count += 1;
+            black_box(runner.proceed(&mut count))
+        })
+    });
+
+    let tree = wide_tree(4, 4, true);
+    c.bench_function("tick wide tree, traced and profiled", |b| {
+        let mut runner = BehaviorRunner::new(tree.clone())
+            .with_trace(16)
+            .with_profile();
+        let mut count = 0u64;
+        b.iter(|| {
+            count += 1;
+            black_box(runner.proceed(&mut count))
+        })
+    });
+
+    let tree = (0..64).map(|_| countdown()).collect::<Parallel<_>>().arc();
+    c.bench_function("resume running parallel", |b| {
+        b.iter_batched(
+            || (BehaviorRunner::new(tree.clone()), 100u64),
+            |(mut runner, mut count)| {
+                while runner.proceed(&mut count).is_none() {}
+                count
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_tick);
+criterion_main!(benches);
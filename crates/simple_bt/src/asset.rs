@@ -0,0 +1,250 @@
+//! Load behavior trees from `.bt.ron` assets, see [`registry`](super::registry) for the format
+
+use crate::plugin::{BehaviorTree, BehaviorTreeSet, EntityContext};
+use crate::registry::BehaviorRegistry;
+use crate::{BehaviorArc, BehaviorNode};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::{BoxedFuture, HashSet},
+};
+use serde::de::DeserializeOwned;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// Loads trees for [`BehaviorTree`]s, and rebuilds them when their asset changes
+pub struct BehaviorTreeAssetPlugin;
+
+impl Plugin for BehaviorTreeAssetPlugin {
+    fn build(&self, app: &mut App) {
+        let mut registry = BehaviorRegistry::<EntityContext>::new();
+        registry
+            .register_blackboard_nodes()
+            .register_time_nodes()
+            .register_random_nodes();
+        app.add_behavior_registry(registry)
+            .add_systems(Update, sync_behavior_tree_sources.before(BehaviorTreeSet));
+    }
+}
+
+pub trait BehaviorTreeApp {
+    /// Load `.bt.ron` assets for contexts of type `B` using `registry`
+    fn add_behavior_registry<B: TypePath + Send + Sync + 'static>(
+        &mut self,
+        registry: BehaviorRegistry<B>,
+    ) -> &mut Self;
+
+    /// See [`BehaviorRegistry::register`]
+    fn register_behavior<B, P, F>(&mut self, name: impl Into<String>, build: F) -> &mut Self
+    where
+        B: 'static,
+        P: DeserializeOwned,
+        F: Fn(P) -> BehaviorArc<B> + Send + Sync + 'static;
+
+    /// See [`BehaviorRegistry::register_leaf`]
+    fn register_behavior_leaf<B, T>(&mut self, name: impl Into<String>) -> &mut Self
+    where
+        B: 'static,
+        T: BehaviorNode<B> + DeserializeOwned + Send + Sync + 'static,
+    {
+        self.register_behavior(name, |leaf: T| leaf.arc())
+    }
+}
+
+impl BehaviorTreeApp for App {
+    fn add_behavior_registry<B: TypePath + Send + Sync + 'static>(
+        &mut self,
+        registry: BehaviorRegistry<B>,
+    ) -> &mut Self {
+        let registry = Arc::new(RwLock::new(registry));
+        self.init_asset::<BehaviorTreeAsset<B>>()
+            .register_asset_loader(BehaviorTreeLoader {
+                registry: registry.clone(),
+            })
+            .insert_resource(SharedBehaviorRegistry(registry))
+    }
+
+    fn register_behavior<B, P, F>(&mut self, name: impl Into<String>, build: F) -> &mut Self
+    where
+        B: 'static,
+        P: DeserializeOwned,
+        F: Fn(P) -> BehaviorArc<B> + Send + Sync + 'static,
+    {
+        self.world
+            .get_resource::<SharedBehaviorRegistry<B>>()
+            .expect("behaviors can only be registered after adding their registry")
+            .0
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .register(name, build);
+        self
+    }
+}
+
+/// The registry trees for contexts of type `B` are loaded with
+#[derive(Resource)]
+pub struct SharedBehaviorRegistry<B>(Arc<RwLock<BehaviorRegistry<B>>>);
+
+/// A tree loaded from a `.bt.ron` file
+#[derive(Asset, TypePath)]
+pub struct BehaviorTreeAsset<B: TypePath + Send + Sync + 'static> {
+    tree: BehaviorArc<B>,
+}
+
+impl<B: TypePath + Send + Sync + 'static> BehaviorTreeAsset<B> {
+    pub fn tree(&self) -> BehaviorArc<B> {
+        self.tree.clone()
+    }
+}
+
+/// Builds the [`BehaviorTree`] of its entity from a loaded tree
+///
+/// The tree is rebuilt (and starts over) whenever the asset changes, halting the old one.
+#[derive(Component)]
+pub struct BehaviorTreeSource(pub Handle<BehaviorTreeAsset<EntityContext>>);
+
+fn sync_behavior_tree_sources(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<BehaviorTreeAsset<EntityContext>>>,
+    assets: Res<Assets<BehaviorTreeAsset<EntityContext>>>,
+    sources: Query<(Entity, Ref<BehaviorTreeSource>, Option<&BehaviorTree>)>,
+) {
+    let changed = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    for (entity, source, tree) in sources.iter() {
+        let needs_tree = !tree.as_ref().is_some_and(|tree| tree.has_tree());
+        if !(source.is_changed() || needs_tree || changed.contains(&source.0.id())) {
+            continue;
+        }
+        let Some(asset) = assets.get(&source.0) else {
+            continue;
+        };
+        match tree {
+            Some(_) => {
+                let tree = asset.tree();
+                commands.add(move |world: &mut World| {
+                    BehaviorTree::replace_tree(world, entity, tree);
+                });
+            }
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(BehaviorTree::new(asset.tree()));
+            }
+        }
+    }
+}
+
+struct BehaviorTreeLoader<B> {
+    registry: Arc<RwLock<BehaviorRegistry<B>>>,
+}
+
+#[non_exhaustive]
+#[derive(thiserror::Error, Debug)]
+enum BehaviorTreeLoaderError {
+    /// An [IO](std::io) Error
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error, which includes problems building the tree
+    #[error("Could not build tree: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+}
+
+impl<B: TypePath + Send + Sync + 'static> AssetLoader for BehaviorTreeLoader<B> {
+    type Asset = BehaviorTreeAsset<B>;
+    type Settings = ();
+    type Error = BehaviorTreeLoaderError;
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let tree = self
+                .registry
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .build_from_bytes(&bytes)?;
+            Ok(BehaviorTreeAsset { tree })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["bt.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BehaviorTreeAsset, BehaviorTreeAssetPlugin, BehaviorTreeSource};
+    use crate::plugin::{BehaviorTree, BehaviorTreePlugin, EntityContext};
+    use crate::{BehaviorNode, NodeResult};
+    use assert2::check;
+    use bevy::prelude::*;
+
+    #[derive(Component)]
+    struct Thoughts(Vec<&'static str>);
+
+    #[derive(Debug)]
+    struct Think(&'static str);
+
+    impl BehaviorNode<EntityContext> for Think {
+        fn tick(&self, context: &mut EntityContext) -> NodeResult<EntityContext> {
+            match context.get_mut::<Thoughts>() {
+                Some(mut thoughts) => {
+                    thoughts.0.push(self.0);
+                    NodeResult::Running(Think(self.0).arc())
+                }
+                None => NodeResult::Failure,
+            }
+        }
+    }
+
+    #[test]
+    fn test_sources_build_trees() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            BehaviorTreePlugin::default(),
+            BehaviorTreeAssetPlugin,
+        ));
+        let handle = app
+            .world
+            .resource_mut::<Assets<BehaviorTreeAsset<EntityContext>>>()
+            .add(BehaviorTreeAsset {
+                tree: Think("hmm").arc(),
+            });
+        let thinker = app
+            .world
+            .spawn((Thoughts(vec![]), BehaviorTreeSource(handle.clone())))
+            .id();
+
+        // The tree is built in time to think the same update
+        app.update();
+        check!(app.world.get::<BehaviorTree>(thinker).is_some());
+        check!(app.world.get::<Thoughts>(thinker).unwrap().0 == ["hmm"]);
+
+        // Changing the asset replaces the tree
+        app.world
+            .resource_mut::<Assets<BehaviorTreeAsset<EntityContext>>>()
+            .insert(
+                &handle,
+                BehaviorTreeAsset {
+                    tree: Think("aha").arc(),
+                },
+            );
+        app.update();
+        app.update();
+        check!(app.world.get::<Thoughts>(thinker).unwrap().0.last() == Some(&"aha"));
+    }
+}
@@ -0,0 +1,368 @@
+//! A typed store of values shared between nodes
+//!
+//! Nodes read and write values through [`BlackboardKey`]s, so leaves don't need
+//! to know the shape of the whole context they are ticked with.
+
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+/// Names a value of type `T` on a [`Blackboard`]
+///
+/// Keys are compared by name, so two keys with the same name but different
+/// types refer to the same slot, and reading with the wrong type yields nothing.
+pub struct BlackboardKey<T> {
+    name: Cow<'static, str>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> BlackboardKey<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Create a key from a name only known at runtime
+    pub fn named(name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: name.into(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<T> Clone for BlackboardKey<T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for BlackboardKey<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "BlackboardKey<{}>({:?})",
+            std::any::type_name::<T>(),
+            self.name
+        )
+    }
+}
+
+type Entries = HashMap<Cow<'static, str>, Box<dyn Any + Send + Sync>>;
+
+/// The owners and names of entered scopes, outermost first
+type ScopePath = Vec<(usize, Cow<'static, str>)>;
+
+#[derive(Default)]
+struct Scope {
+    owner: usize,
+    name: Cow<'static, str>,
+    entries: Entries,
+}
+
+/// Stores values for nodes to share
+///
+/// A blackboard is a stack of scopes. Reads look through every scope, innermost
+/// first, while writes go to the innermost scope, shadowing outer values.
+/// Scopes are generally entered through [`Scoped`](super::composite::Scoped),
+/// which gives a subtree its own values.
+pub struct Blackboard {
+    /// Innermost scope last, the root scope is always present
+    scopes: Vec<Scope>,
+    /// Entries of scopes that were exited while their subtree was still running,
+    /// keyed by the owners and names of the path to the scope
+    suspended: HashMap<ScopePath, Entries>,
+}
+
+impl Default for Blackboard {
+    fn default() -> Self {
+        Self {
+            scopes: vec![Scope::default()],
+            suspended: HashMap::new(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Blackboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Blackboard")
+            .field("scope", &self.scope_path())
+            .field(
+                "keys",
+                &self
+                    .scopes
+                    .iter()
+                    .map(|scope| scope.entries.keys().collect::<Vec<_>>())
+                    .collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl Blackboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the value for `key` from the innermost scope that has it
+    pub fn get<T: Any>(&self, key: &BlackboardKey<T>) -> Option<&T> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.entries.get(key.name()))
+            .and_then(|value| value.downcast_ref())
+    }
+
+    /// Mutably get the value for `key` from the innermost scope that has it
+    pub fn get_mut<T: Any>(&mut self, key: &BlackboardKey<T>) -> Option<&mut T> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.entries.get_mut(key.name()))
+            .and_then(|value| value.downcast_mut())
+    }
+
+    /// Get a copy of the value for `key`, or its default if it isn't set
+    pub fn get_or_default<T: Any + Clone + Default>(&self, key: &BlackboardKey<T>) -> T {
+        self.get(key).cloned().unwrap_or_default()
+    }
+
+    pub fn contains<T: Any>(&self, key: &BlackboardKey<T>) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Set the value for `key` in the innermost scope, returning the value
+    /// it replaced in that scope
+    pub fn set<T: Any + Send + Sync>(&mut self, key: &BlackboardKey<T>, value: T) -> Option<T> {
+        self.innermost()
+            .entries
+            .insert(key.name.clone(), Box::new(value))
+            .and_then(|old| old.downcast().ok())
+            .map(|old| *old)
+    }
+
+    /// Remove the value for `key` from the innermost scope
+    pub fn remove<T: Any>(&mut self, key: &BlackboardKey<T>) -> Option<T> {
+        self.innermost()
+            .entries
+            .remove(key.name())
+            .and_then(|old| old.downcast().ok())
+            .map(|old| *old)
+    }
+
+    /// Enter a new innermost scope
+    ///
+    /// If a scope with the same path was exited while still running,
+    /// its values are restored.
+    pub fn enter_scope(&mut self, name: impl Into<Cow<'static, str>>) {
+        self.enter_owned_scope(0, name);
+    }
+
+    /// Enter a new innermost scope on behalf of `owner`
+    ///
+    /// Scopes with different owners never share values, even if they have the same name,
+    /// as with two [`Scoped`](super::composite::Scoped) nodes in parallel branches.
+    pub fn enter_owned_scope(&mut self, owner: usize, name: impl Into<Cow<'static, str>>) {
+        self.scopes.push(Scope {
+            owner,
+            name: name.into(),
+            entries: Entries::new(),
+        });
+        if let Some(entries) = self.suspended.remove(&self.path()) {
+            self.innermost().entries = entries;
+        }
+    }
+
+    /// Exit the innermost scope
+    ///
+    /// If `keep` is set, its values will be restored when the scope is next entered.
+    /// Otherwise they are dropped, along with those of any scopes suspended inside it.
+    pub fn exit_scope(&mut self, keep: bool) {
+        debug_assert!(self.scopes.len() > 1, "exited the root blackboard scope");
+        if self.scopes.len() <= 1 {
+            return;
+        }
+        let path = self.path();
+        if let Some(scope) = self.scopes.pop() {
+            if keep {
+                self.suspended.insert(path, scope.entries);
+            } else {
+                self.suspended
+                    .retain(|suspended, _| !suspended.starts_with(&path));
+            }
+        }
+    }
+
+    /// How many scopes were exited while their subtree was still running
+    pub fn suspended_scopes(&self) -> usize {
+        self.suspended.len()
+    }
+
+    fn path(&self) -> ScopePath {
+        self.scopes
+            .iter()
+            .skip(1)
+            .map(|scope| (scope.owner, scope.name.clone()))
+            .collect()
+    }
+
+    /// The names of all entered scopes, joined by `/`
+    pub fn scope_path(&self) -> String {
+        self.scopes
+            .iter()
+            .skip(1)
+            .map(|scope| scope.name.as_ref())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn innermost(&mut self) -> &mut Scope {
+        self.scopes
+            .last_mut()
+            .expect("blackboard root scope is always present")
+    }
+}
+
+/// Contexts that nodes can find a [`Blackboard`] in
+pub trait HasBlackboard {
+    fn blackboard(&self) -> &Blackboard;
+    fn blackboard_mut(&mut self) -> &mut Blackboard;
+}
+
+impl HasBlackboard for Blackboard {
+    fn blackboard(&self) -> &Blackboard {
+        self
+    }
+
+    fn blackboard_mut(&mut self) -> &mut Blackboard {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Blackboard, BlackboardKey};
+    use assert2::check;
+
+    const HEALTH: BlackboardKey<u32> = BlackboardKey::new("health");
+    const NAME: BlackboardKey<String> = BlackboardKey::new("name");
+
+    #[test]
+    fn test_typed_keys() {
+        let mut blackboard = Blackboard::new();
+        check!(blackboard.get(&HEALTH).is_none());
+        check!(blackboard.set(&HEALTH, 3).is_none());
+        check!(blackboard.set(&NAME, "Polly".to_string()).is_none());
+        check!(blackboard.get(&HEALTH) == Some(&3));
+        check!(blackboard.get(&NAME).map(String::as_str) == Some("Polly"));
+
+        *blackboard.get_mut(&HEALTH).unwrap() -= 1;
+        check!(blackboard.set(&HEALTH, 10) == Some(2));
+        check!(blackboard.remove(&HEALTH) == Some(10));
+        check!(!blackboard.contains(&HEALTH));
+    }
+
+    #[test]
+    fn test_mismatched_type() {
+        let mut blackboard = Blackboard::new();
+        blackboard.set(&HEALTH, 3);
+        let wrong = BlackboardKey::<f32>::named("health");
+        check!(blackboard.get(&wrong).is_none());
+        check!(blackboard.get_or_default(&wrong) == 0.0);
+    }
+
+    #[test]
+    fn test_scopes_shadow() {
+        let mut blackboard = Blackboard::new();
+        blackboard.set(&HEALTH, 3);
+        blackboard.enter_scope("inner");
+        check!(blackboard.scope_path() == "inner");
+        // Outer values are visible...
+        check!(blackboard.get(&HEALTH) == Some(&3));
+        // ...until shadowed
+        blackboard.set(&HEALTH, 5);
+        blackboard.set(&NAME, "inner".to_string());
+        check!(blackboard.get(&HEALTH) == Some(&5));
+        blackboard.exit_scope(false);
+        check!(blackboard.get(&HEALTH) == Some(&3));
+        check!(!blackboard.contains(&NAME));
+    }
+
+    #[test]
+    fn test_scopes_suspend() {
+        let mut blackboard = Blackboard::new();
+        blackboard.enter_scope("a");
+        blackboard.enter_scope("b");
+        blackboard.set(&HEALTH, 1);
+        blackboard.exit_scope(true);
+        blackboard.exit_scope(true);
+
+        // Only the same path restores the values
+        blackboard.enter_scope("b");
+        check!(!blackboard.contains(&HEALTH));
+        blackboard.exit_scope(false);
+
+        blackboard.enter_scope("a");
+        blackboard.enter_scope("b");
+        check!(blackboard.get(&HEALTH) == Some(&1));
+        blackboard.exit_scope(false);
+        blackboard.enter_scope("b");
+        check!(!blackboard.contains(&HEALTH));
+    }
+
+    #[test]
+    fn test_nested_same_names() {
+        let mut blackboard = Blackboard::new();
+        blackboard.set(&HEALTH, 1);
+        blackboard.enter_scope("a");
+        blackboard.set(&HEALTH, 2);
+        blackboard.enter_scope("a");
+        blackboard.set(&HEALTH, 3);
+        blackboard.exit_scope(true);
+        check!(blackboard.get(&HEALTH) == Some(&2));
+        blackboard.exit_scope(true);
+        check!(blackboard.get(&HEALTH) == Some(&1));
+
+        // A scope named like a path is a scope of its own
+        blackboard.enter_scope("a/a");
+        check!(blackboard.get(&HEALTH) == Some(&1));
+        blackboard.exit_scope(false);
+        check!(blackboard.suspended_scopes() == 2);
+
+        blackboard.enter_scope("a");
+        check!(blackboard.get(&HEALTH) == Some(&2));
+        blackboard.enter_scope("a");
+        check!(blackboard.get(&HEALTH) == Some(&3));
+        blackboard.exit_scope(true);
+        // Closing a scope drops the scopes suspended inside it
+        blackboard.exit_scope(false);
+        check!(blackboard.get(&HEALTH) == Some(&1));
+        check!(blackboard.suspended_scopes() == 0);
+    }
+
+    #[test]
+    fn test_scope_owners() {
+        let mut blackboard = Blackboard::new();
+        blackboard.enter_owned_scope(1, "a");
+        blackboard.set(&HEALTH, 1);
+        blackboard.exit_scope(true);
+
+        blackboard.enter_owned_scope(2, "a");
+        check!(blackboard.scope_path() == "a");
+        check!(!blackboard.contains(&HEALTH));
+        blackboard.exit_scope(false);
+
+        blackboard.enter_owned_scope(1, "a");
+        check!(blackboard.get(&HEALTH) == Some(&1));
+    }
+}
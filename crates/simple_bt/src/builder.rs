@@ -0,0 +1,188 @@
+//! Shorthand for writing trees in code
+//!
+//! Trees can be written about as they are drawn, with [`seq!`](crate::seq),
+//! [`sel!`](crate::sel) and [`par!`](crate::par) for composites, and the
+//! [`BehaviorExt`] methods for decorators:
+//!
+//! ```ignore
+//! let patrol = seq![walk_left, Wait::new(SECOND), walk_right].repeat();
+//! let idle = sel![patrol, Condition::from_fn(|ctx: &Ctx| ctx.tired)].invert();
+//! ```
+
+use crate::blackboard::HasBlackboard;
+use crate::composite::{
+    Cooldown, Delay, Inverter, LimitedRepeated, Repeated, RepeatedUntilFailure, Scoped, Succeeder,
+    Timeout,
+};
+use crate::time::HasTime;
+use crate::{BehaviorArc, BehaviorNode};
+use std::borrow::Cow;
+use std::time::Duration;
+
+/// Marks [`BehaviorExt`] as implemented for a node
+pub struct IsNode;
+
+/// Marks [`BehaviorExt`] as implemented for a [`BehaviorArc`]
+pub struct IsArc;
+
+/// Decorators for nodes and [`BehaviorArc`]s alike
+///
+/// `Marker` only tells the two implementations apart, and is always inferred.
+pub trait BehaviorExt<B, Marker>: Sized {
+    fn into_behavior(self) -> BehaviorArc<B>;
+
+    /// Wrap in an [`Inverter`]
+    fn invert(self) -> Inverter<B> {
+        Inverter::new(self.into_behavior())
+    }
+
+    /// Wrap in a [`Succeeder`]
+    fn succeed(self) -> Succeeder<B> {
+        Succeeder::new(self.into_behavior())
+    }
+
+    /// Wrap in a [`Repeated`]
+    fn repeat(self) -> Repeated<B> {
+        Repeated::new(self.into_behavior())
+    }
+
+    /// Wrap in a [`LimitedRepeated`]
+    fn repeat_n(self, limit: usize) -> LimitedRepeated<B> {
+        LimitedRepeated::new(self.into_behavior(), limit)
+    }
+
+    /// Wrap in a [`RepeatedUntilFailure`]
+    fn repeat_until_failure(self) -> RepeatedUntilFailure<B> {
+        RepeatedUntilFailure::new(self.into_behavior())
+    }
+
+    /// Wrap in a [`Scoped`]
+    fn scoped(self, name: impl Into<Cow<'static, str>>) -> Scoped<B>
+    where
+        B: HasBlackboard,
+    {
+        Scoped::new(name, self.into_behavior())
+    }
+
+    /// Wrap in a [`Timeout`]
+    fn timeout(self, duration: Duration) -> Timeout<B>
+    where
+        B: HasTime,
+    {
+        Timeout::new(duration, self.into_behavior())
+    }
+
+    /// Wrap in a [`Cooldown`]
+    fn cooldown(self, duration: Duration) -> Cooldown<B>
+    where
+        B: HasTime,
+    {
+        Cooldown::new(duration, self.into_behavior())
+    }
+
+    /// Wrap in a [`Delay`]
+    fn delay(self, duration: Duration) -> Delay<B>
+    where
+        B: HasTime,
+    {
+        Delay::new(duration, self.into_behavior())
+    }
+}
+
+impl<B, N: BehaviorNode<B> + Send + Sync + 'static> BehaviorExt<B, IsNode> for N {
+    fn into_behavior(self) -> BehaviorArc<B> {
+        self.arc()
+    }
+}
+
+impl<B> BehaviorExt<B, IsArc> for BehaviorArc<B> {
+    fn into_behavior(self) -> BehaviorArc<B> {
+        self
+    }
+}
+
+/// A [`Sequence`](crate::composite::Sequence) of nodes or [`BehaviorArc`]s
+#[macro_export]
+macro_rules! seq {
+    ($($child:expr),+ $(,)?) => {
+        <$crate::composite::Sequence<_> as ::std::iter::FromIterator<_>>::from_iter([
+            $($crate::builder::BehaviorExt::into_behavior($child)),+
+        ])
+    };
+}
+
+/// A [`Selector`](crate::composite::Selector) of nodes or [`BehaviorArc`]s
+#[macro_export]
+macro_rules! sel {
+    ($($child:expr),+ $(,)?) => {
+        <$crate::composite::Selector<_> as ::std::iter::FromIterator<_>>::from_iter([
+            $($crate::builder::BehaviorExt::into_behavior($child)),+
+        ])
+    };
+}
+
+/// A [`Parallel`](crate::composite::Parallel) of nodes or [`BehaviorArc`]s
+#[macro_export]
+macro_rules! par {
+    ($($child:expr),+ $(,)?) => {
+        <$crate::composite::Parallel<_> as ::std::iter::FromIterator<_>>::from_iter([
+            $($crate::builder::BehaviorExt::into_behavior($child)),+
+        ])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BehaviorExt;
+    use crate::inspect::NodeStatus;
+    use crate::leaf::{Action, Condition};
+    use crate::{BehaviorNode, BehaviorRunner};
+    use assert2::check;
+
+    fn push(id: u32) -> Action<Vec<u32>> {
+        Action::from_fn(move |log: &mut Vec<u32>| {
+            log.push(id);
+            NodeStatus::Success
+        })
+    }
+
+    #[test]
+    fn test_structure() {
+        let tree = seq![push(1), sel![push(2).invert(), push(3).arc()],]
+            .repeat_n(2)
+            .invert();
+        check!(tree.name() == "Inverter");
+        let repeated = &tree.children()[0];
+        check!(repeated.name() == "LimitedRepeated(2)");
+        let sequence = &repeated.children()[0];
+        check!(sequence.name() == "Sequence");
+        check!(
+            sequence.children()[1]
+                .children()
+                .iter()
+                .map(|child| child.name())
+                .collect::<Vec<_>>()
+                == ["Inverter", "Action"]
+        );
+
+        let mut log = vec![];
+        let mut runner = BehaviorRunner::new(tree.arc());
+        while runner.proceed(&mut log).is_none() {}
+        check!(log == [1, 2, 3, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_conditions() {
+        let tree = sel![
+            seq![Condition::from_fn(|log: &Vec<u32>| log.len() > 1), push(2)],
+            push(1),
+        ]
+        .arc();
+        let mut log = vec![];
+        let mut runner = BehaviorRunner::new(tree);
+        check!(runner.proceed(&mut log) == Some(true));
+        check!(runner.proceed(&mut log) == Some(true));
+        check!(runner.proceed(&mut log) == Some(true));
+        check!(log == [1, 1, 2]);
+    }
+}
@@ -0,0 +1,27 @@
+//! Implementations of composite nodes
+
+// We do a little thin runner so nodes are thick
+
+mod inverter;
+mod parallel;
+mod random;
+mod reactive;
+mod repeater;
+mod scoped;
+mod selector;
+mod sequence;
+mod succeeder;
+mod timed;
+mod utility;
+
+pub use inverter::Inverter;
+pub use parallel::{Parallel, ParallelPolicy};
+pub use random::{RandomSelector, ShuffledSequence, WeightedChoice};
+pub use reactive::{ReactiveSelector, ReactiveSequence};
+pub use repeater::{LimitedRepeated, Repeated, RepeatedUntilFailure};
+pub use scoped::Scoped;
+pub use selector::Selector;
+pub use sequence::Sequence;
+pub use succeeder::Succeeder;
+pub use timed::{Cooldown, Delay, Timeout, Wait};
+pub use utility::{Scorer, UtilitySelector};
@@ -0,0 +1,80 @@
+use crate::inspect::tick_child;
+use crate::snapshot::{restore_decorated, NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+
+/// Inverts the result of its child
+///
+/// Success becomes failure, failure success.
+pub struct Inverter<B> {
+    child: BehaviorArc<B>,
+}
+
+impl<B> std::fmt::Debug for Inverter<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Inverter")
+            .field("child", &self.child)
+            .finish()
+    }
+}
+
+impl<B> Inverter<B> {
+    pub fn new(child: BehaviorArc<B>) -> Self {
+        Self { child }
+    }
+}
+
+impl<B: 'static> BehaviorNode<B> for Inverter<B> {
+    fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
+        match tick_child(0, self.child.as_ref(), blackboard) {
+            NodeResult::Success => NodeResult::Failure,
+            NodeResult::Failure => NodeResult::Success,
+            NodeResult::Running(resume) => NodeResult::Running(Inverter::new(resume).arc()),
+        }
+    }
+
+    fn halt(&self, blackboard: &mut B) {
+        self.child.halt(blackboard);
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        vec![self.child.clone()]
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        Ok(NodeState::new().with_child(0, self.child.snapshot(context)?))
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        Ok(Inverter::new(restore_decorated(self, &self.child, state, context)?).arc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Inverter;
+    use crate::test_support::After;
+    use crate::{BehaviorNode, BehaviorRunner};
+    use assert2::check;
+
+    #[test]
+    fn test_inverter() {
+        let mut count = 0;
+        for result in [true, false] {
+            let tree = Inverter::new(After::new(1, result).arc()).arc();
+            let mut runner = BehaviorRunner::new(tree);
+            check!(runner.proceed(&mut count) == Some(!result));
+        }
+        check!(count == 2);
+    }
+
+    #[test]
+    fn test_inverter_resumes() {
+        let mut count = 0;
+        let tree = Inverter::new(After::succeed(3).arc()).arc();
+        let mut runner = BehaviorRunner::new(tree);
+        check!(runner.proceed(&mut count).is_none());
+        check!(runner.proceed(&mut count).is_none());
+        check!(runner.proceed(&mut count) == Some(false));
+        check!(count == 3);
+    }
+}
@@ -0,0 +1,291 @@
+use crate::inspect::tick_child;
+use crate::snapshot::{restore_child, NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// How a [`Parallel`] decides its result
+///
+/// The policy names the condition that resolves the node. If every child
+/// completes without that condition being met, the node resolves the other way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+#[cfg_attr(feature = "bevy", derive(bevy::reflect::Reflect))]
+pub enum ParallelPolicy {
+    /// Succeed on the tick one child succeeds
+    SucceedOnOne,
+    /// Succeed if every child succeeds, letting all children finish
+    #[default]
+    SucceedOnAll,
+    /// Fail on the tick one child fails
+    FailOnOne,
+    /// Fail if every child fails, letting all children finish
+    FailOnAll,
+}
+
+impl ParallelPolicy {
+    /// `None` means we are still running
+    fn resolve(self, succeeded: usize, failed: usize, total: usize) -> Option<bool> {
+        let done = succeeded + failed >= total;
+        match self {
+            ParallelPolicy::SucceedOnOne if succeeded > 0 => Some(true),
+            ParallelPolicy::SucceedOnOne => done.then_some(false),
+            ParallelPolicy::SucceedOnAll => done.then_some(succeeded == total),
+            ParallelPolicy::FailOnOne if failed > 0 => Some(false),
+            ParallelPolicy::FailOnOne => done.then_some(true),
+            ParallelPolicy::FailOnAll => done.then_some(failed != total),
+        }
+    }
+}
+
+/// Ticks every child each tick
+pub struct Parallel<B> {
+    pub(crate) sub: Arc<[BehaviorArc<B>]>,
+    pub(crate) policy: ParallelPolicy,
+}
+
+impl<B> std::fmt::Debug for Parallel<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("Parallel<{:p}>", self.sub.as_ref()))
+            .field("sub", &self.sub)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+impl<B, I: Into<BehaviorArc<B>>> FromIterator<I> for Parallel<B> {
+    fn from_iter<T: IntoIterator<Item = I>>(iter: T) -> Self {
+        Self {
+            sub: Arc::from(iter.into_iter().map(Into::into).collect::<Vec<_>>()),
+            policy: ParallelPolicy::default(),
+        }
+    }
+}
+
+impl<B> Parallel<B> {
+    pub fn with_policy(self, policy: ParallelPolicy) -> Self {
+        Self { policy, ..self }
+    }
+}
+
+impl<B: 'static> Parallel<B> {
+    /// Tick each of `running` (`None` for completed children), and
+    /// resolve with `policy`
+    ///
+    /// Every child is ticked before resolving, and any still running are then halted.
+    pub(crate) fn step(
+        seq: Arc<[BehaviorArc<B>]>,
+        policy: ParallelPolicy,
+        running: impl Iterator<Item = Option<BehaviorArc<B>>>,
+        mut succeeded: usize,
+        mut failed: usize,
+        blackboard: &mut B,
+    ) -> NodeResult<B> {
+        let mut resumes = Vec::with_capacity(seq.len());
+        for (idx, sub) in running.enumerate() {
+            let resume = match sub.map(|sub| tick_child(idx, sub.as_ref(), blackboard)) {
+                Some(NodeResult::Success) => {
+                    succeeded += 1;
+                    None
+                }
+                Some(NodeResult::Failure) => {
+                    failed += 1;
+                    None
+                }
+                Some(NodeResult::Running(resume)) => Some(resume),
+                None => None,
+            };
+            resumes.push(resume);
+        }
+        let Some(success) = policy.resolve(succeeded, failed, seq.len()) else {
+            return NodeResult::Running(
+                ParallelResume {
+                    seq,
+                    policy,
+                    resumes: resumes.into(),
+                    succeeded,
+                    failed,
+                }
+                .arc(),
+            );
+        };
+        // Children that are still running won't be ticked again
+        for sub in resumes.into_iter().flatten() {
+            sub.halt(blackboard);
+        }
+        if success {
+            NodeResult::Success
+        } else {
+            NodeResult::Failure
+        }
+    }
+}
+
+impl<B: 'static> BehaviorNode<B> for Parallel<B> {
+    fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
+        Self::step(
+            self.sub.clone(),
+            self.policy,
+            self.sub.iter().cloned().map(Some),
+            0,
+            0,
+            blackboard,
+        )
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        format!("Parallel({:?})", self.policy).into()
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.sub.to_vec()
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let (succeeded, failed) = state.data()?;
+        let mut resumes = vec![None; self.sub.len()];
+        for (idx, child) in state.children() {
+            resumes[*idx] = Some(restore_child(self, &self.sub, *idx, child, context)?);
+        }
+        Ok(ParallelResume {
+            seq: self.sub.clone(),
+            policy: self.policy,
+            resumes: resumes.into(),
+            succeeded,
+            failed,
+        }
+        .arc())
+    }
+}
+
+pub(crate) struct ParallelResume<B> {
+    pub(crate) seq: Arc<[BehaviorArc<B>]>,
+    pub(crate) policy: ParallelPolicy,
+    /// One continuation per child, `None` if that child has completed
+    pub(crate) resumes: Arc<[Option<BehaviorArc<B>>]>,
+    pub(crate) succeeded: usize,
+    pub(crate) failed: usize,
+}
+
+impl<B> std::fmt::Debug for ParallelResume<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("ParallelResume<{:p}>", self.seq.as_ref()))
+            .field("resumes", &self.resumes)
+            .field("policy", &self.policy)
+            .field("succeeded", &self.succeeded)
+            .field("failed", &self.failed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B: 'static> BehaviorNode<B> for ParallelResume<B> {
+    fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
+        Parallel::step(
+            self.seq.clone(),
+            self.policy,
+            self.resumes.iter().cloned(),
+            self.succeeded,
+            self.failed,
+            blackboard,
+        )
+    }
+
+    fn halt(&self, blackboard: &mut B) {
+        for resume in self.resumes.iter().flatten() {
+            resume.halt(blackboard);
+        }
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        format!("Parallel({:?})", self.policy).into()
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.seq.to_vec()
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        let mut state = NodeState::new().with_data(&(self.succeeded, self.failed))?;
+        for (idx, resume) in self.resumes.iter().enumerate() {
+            if let Some(resume) = resume {
+                state = state.with_child(idx, resume.snapshot(context)?);
+            }
+        }
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Parallel, ParallelPolicy};
+    use crate::test_support::{run, After, HALTED};
+    use crate::BehaviorNode;
+    use assert2::check;
+
+    fn parallel(policy: ParallelPolicy, tree: &[(usize, bool)]) -> (bool, Vec<usize>) {
+        let tree = tree
+            .iter()
+            .enumerate()
+            .map(|(id, &(ticks, result))| After::new(ticks, result).with_id(id).arc())
+            .collect::<Parallel<_>>()
+            .with_policy(policy)
+            .arc();
+        let mut log = vec![];
+        (run(tree, &mut log), log)
+    }
+
+    #[test]
+    fn test_parallel_ticks_every_child() {
+        let (res, log) = parallel(ParallelPolicy::SucceedOnAll, &[(2, true), (3, true)]);
+        check!(res);
+        // Completed children are not ticked again
+        check!(log == [0, 1, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_parallel_succeed_on_one() {
+        let (res, log) = parallel(ParallelPolicy::SucceedOnOne, &[(3, true), (2, true)]);
+        check!(res);
+        // The child still running is halted
+        check!(log == [0, 1, 0, 1, HALTED]);
+
+        // Later children are still ticked on the tick an earlier one resolves
+        let (res, log) = parallel(ParallelPolicy::SucceedOnOne, &[(1, true), (2, true)]);
+        check!(res);
+        check!(log == [0, 1, HALTED + 1]);
+
+        let (res, _) = parallel(ParallelPolicy::SucceedOnOne, &[(1, false), (2, false)]);
+        check!(!res);
+    }
+
+    #[test]
+    fn test_parallel_succeed_on_all() {
+        // A failure doesn't stop the others from finishing
+        let (res, log) = parallel(ParallelPolicy::SucceedOnAll, &[(1, false), (2, true)]);
+        check!(!res);
+        check!(log == [0, 1, 1]);
+    }
+
+    #[test]
+    fn test_parallel_fail_on_one() {
+        let (res, log) = parallel(ParallelPolicy::FailOnOne, &[(3, true), (2, false)]);
+        check!(!res);
+        check!(log == [0, 1, 0, 1, HALTED]);
+
+        let (res, log) = parallel(ParallelPolicy::FailOnOne, &[(1, false), (1, true)]);
+        check!(!res);
+        check!(log == [0, 1]);
+
+        let (res, _) = parallel(ParallelPolicy::FailOnOne, &[(1, true), (2, true)]);
+        check!(res);
+    }
+
+    #[test]
+    fn test_parallel_fail_on_all() {
+        let (res, log) = parallel(ParallelPolicy::FailOnAll, &[(1, true), (2, false)]);
+        check!(res);
+        check!(log == [0, 1, 1]);
+
+        let (res, _) = parallel(ParallelPolicy::FailOnAll, &[(1, false), (3, false)]);
+        check!(!res);
+    }
+}
@@ -0,0 +1,433 @@
+use crate::inspect::tick_child;
+use crate::random::HasRng;
+use crate::snapshot::{mismatch, restore_only_child, NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Tick the children of `seq` in `order`, starting at `position`, moving on while
+/// they complete with `proceed`
+///
+/// `resume` continues the child at `position`, if it was running.
+fn step<B: HasRng + 'static>(
+    seq: &Arc<[BehaviorArc<B>]>,
+    order: &Arc<[usize]>,
+    position: usize,
+    mut resume: Option<&BehaviorArc<B>>,
+    proceed: bool,
+    context: &mut B,
+) -> NodeResult<B> {
+    for (position, &idx) in order.iter().enumerate().skip(position) {
+        let sub = resume.take().unwrap_or(&seq[idx]);
+        let success = match tick_child(idx, sub.as_ref(), context) {
+            NodeResult::Success => true,
+            NodeResult::Failure => false,
+            NodeResult::Running(resume) => {
+                return NodeResult::Running(
+                    ShuffledResume {
+                        seq: seq.clone(),
+                        order: order.clone(),
+                        position,
+                        resume,
+                        proceed,
+                    }
+                    .arc(),
+                )
+            }
+        };
+        if success != proceed {
+            return if success {
+                NodeResult::Success
+            } else {
+                NodeResult::Failure
+            };
+        }
+    }
+    if proceed {
+        NodeResult::Success
+    } else {
+        NodeResult::Failure
+    }
+}
+
+fn shuffled<B: HasRng>(len: usize, context: &mut B) -> Arc<[usize]> {
+    let mut order = (0..len).collect::<Vec<_>>();
+    order.shuffle(context.rng());
+    order.into()
+}
+
+/// Restore a [`ShuffledResume`] of `seq`, as saved by it
+fn restore_shuffled<B: HasRng + 'static>(
+    parent: &dyn BehaviorNode<B>,
+    seq: &Arc<[BehaviorArc<B>]>,
+    proceed: bool,
+    state: &NodeState,
+    context: &B,
+) -> Result<BehaviorArc<B>, SnapshotError> {
+    let (order, position): (Vec<usize>, usize) = state.data()?;
+    let (index, resume) =
+        restore_only_child(parent, seq, state, context)?.ok_or_else(|| mismatch(parent))?;
+    // Every child must come up exactly once, or `step` would skip or overrun them
+    let mut sorted = order.clone();
+    sorted.sort_unstable();
+    if !sorted.into_iter().eq(0..seq.len()) || order.get(position) != Some(&index) {
+        return Err(mismatch(parent));
+    }
+    Ok(ShuffledResume {
+        seq: seq.clone(),
+        order: order.into(),
+        position,
+        resume,
+        proceed,
+    }
+    .arc())
+}
+
+/// A [`Selector`](super::Selector) that tries its children in a random order
+pub struct RandomSelector<B> {
+    pub(crate) sub: Arc<[BehaviorArc<B>]>,
+}
+
+impl<B> std::fmt::Debug for RandomSelector<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("RandomSelector<{:p}>", self.sub.as_ref()))
+            .field("sub", &self.sub)
+            .finish()
+    }
+}
+
+impl<B, I: Into<BehaviorArc<B>>> FromIterator<I> for RandomSelector<B> {
+    fn from_iter<T: IntoIterator<Item = I>>(iter: T) -> Self {
+        Self {
+            sub: Arc::from(iter.into_iter().map(Into::into).collect::<Vec<_>>()),
+        }
+    }
+}
+
+impl<B: HasRng + 'static> BehaviorNode<B> for RandomSelector<B> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        let order = shuffled(self.sub.len(), context);
+        step(&self.sub, &order, 0, None, false, context)
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.sub.to_vec()
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        restore_shuffled(self, &self.sub, false, state, context)
+    }
+}
+
+/// A [`Sequence`](super::Sequence) that runs its children in a random order
+pub struct ShuffledSequence<B> {
+    pub(crate) sub: Arc<[BehaviorArc<B>]>,
+}
+
+impl<B> std::fmt::Debug for ShuffledSequence<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("ShuffledSequence<{:p}>", self.sub.as_ref()))
+            .field("sub", &self.sub)
+            .finish()
+    }
+}
+
+impl<B, I: Into<BehaviorArc<B>>> FromIterator<I> for ShuffledSequence<B> {
+    fn from_iter<T: IntoIterator<Item = I>>(iter: T) -> Self {
+        Self {
+            sub: Arc::from(iter.into_iter().map(Into::into).collect::<Vec<_>>()),
+        }
+    }
+}
+
+impl<B: HasRng + 'static> BehaviorNode<B> for ShuffledSequence<B> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        let order = shuffled(self.sub.len(), context);
+        step(&self.sub, &order, 0, None, true, context)
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.sub.to_vec()
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        restore_shuffled(self, &self.sub, true, state, context)
+    }
+}
+
+/// Continues a [`RandomSelector`] or [`ShuffledSequence`] in the order it drew
+pub(crate) struct ShuffledResume<B> {
+    pub(crate) seq: Arc<[BehaviorArc<B>]>,
+    pub(crate) order: Arc<[usize]>,
+    pub(crate) position: usize,
+    pub(crate) resume: BehaviorArc<B>,
+    /// Whether we are a sequence, which moves on while children succeed
+    pub(crate) proceed: bool,
+}
+
+impl<B> std::fmt::Debug for ShuffledResume<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("ShuffledResume<{:p}>", self.seq.as_ref()))
+            .field("resume", &self.resume)
+            .field("order", &self.order)
+            .field("position", &self.position)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B: HasRng + 'static> BehaviorNode<B> for ShuffledResume<B> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        step(
+            &self.seq,
+            &self.order,
+            self.position,
+            Some(&self.resume),
+            self.proceed,
+            context,
+        )
+    }
+
+    fn halt(&self, context: &mut B) {
+        self.resume.halt(context);
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        if self.proceed {
+            "ShuffledSequence".into()
+        } else {
+            "RandomSelector".into()
+        }
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.seq.to_vec()
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        NodeState::new()
+            .with_child(self.order[self.position], self.resume.snapshot(context)?)
+            .with_data(&(self.order.as_ref(), self.position))
+    }
+}
+
+/// Runs one of its children, picked at random by weight
+///
+/// Fails if no child has any weight.
+pub struct WeightedChoice<B> {
+    options: Arc<[(f32, BehaviorArc<B>)]>,
+    /// The child we picked, if it is running
+    resume: Option<(usize, BehaviorArc<B>)>,
+}
+
+impl<B> std::fmt::Debug for WeightedChoice<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("WeightedChoice<{:p}>", self.options.as_ref()))
+            .field("options", &self.options)
+            .field("resume", &self.resume)
+            .finish()
+    }
+}
+
+impl<B, I: Into<BehaviorArc<B>>> FromIterator<(f32, I)> for WeightedChoice<B> {
+    fn from_iter<T: IntoIterator<Item = (f32, I)>>(iter: T) -> Self {
+        Self {
+            options: Arc::from(
+                iter.into_iter()
+                    .map(|(weight, sub)| (weight, sub.into()))
+                    .collect::<Vec<_>>(),
+            ),
+            resume: None,
+        }
+    }
+}
+
+impl<B: HasRng + 'static> BehaviorNode<B> for WeightedChoice<B> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        let (idx, result) = match self.resume.as_ref() {
+            Some((idx, resume)) => (*idx, tick_child(*idx, resume.as_ref(), context)),
+            None => {
+                let Ok(weights) = WeightedIndex::new(self.options.iter().map(|(weight, _)| weight))
+                else {
+                    return NodeResult::Failure;
+                };
+                let idx = weights.sample(context.rng());
+                (idx, tick_child(idx, self.options[idx].1.as_ref(), context))
+            }
+        };
+        match result {
+            NodeResult::Running(resume) => NodeResult::Running(
+                Self {
+                    options: self.options.clone(),
+                    resume: Some((idx, resume)),
+                }
+                .arc(),
+            ),
+            result => result,
+        }
+    }
+
+    fn halt(&self, context: &mut B) {
+        if let Some((_, resume)) = self.resume.as_ref() {
+            resume.halt(context);
+        }
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.options.iter().map(|(_, sub)| sub.clone()).collect()
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        let (idx, resume) = self.resume.as_ref().ok_or_else(|| mismatch(self))?;
+        Ok(NodeState::new().with_child(*idx, resume.snapshot(context)?))
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let (index, resume) = restore_only_child(self, &self.children(), state, context)?
+            .ok_or_else(|| mismatch(self))?;
+        Ok(Self {
+            options: self.options.clone(),
+            resume: Some((index, resume)),
+        }
+        .arc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RandomSelector, ShuffledSequence, WeightedChoice};
+    use crate::random::HasRng;
+    use crate::snapshot::{NodeState, RunnerState, SnapshotError};
+    use crate::test_support::{run, After, Record};
+    use crate::{BehaviorArc, BehaviorNode, BehaviorRunner};
+    use assert2::check;
+    use rand::rngs::StdRng;
+    use rand::{RngCore, SeedableRng};
+
+    struct Context {
+        rng: StdRng,
+        log: Vec<usize>,
+    }
+
+    impl Context {
+        fn seeded(seed: u64) -> Self {
+            Self {
+                rng: StdRng::seed_from_u64(seed),
+                log: vec![],
+            }
+        }
+    }
+
+    impl HasRng for Context {
+        fn rng(&mut self) -> &mut dyn RngCore {
+            &mut self.rng
+        }
+    }
+
+    impl Record for Context {
+        fn ticked(&mut self, id: usize) {
+            self.log.push(id);
+        }
+    }
+
+    fn run_seeded(tree: BehaviorArc<Context>, seed: u64) -> (bool, Vec<usize>) {
+        let mut context = Context::seeded(seed);
+        (run(tree, &mut context), context.log)
+    }
+
+    /// A shuffled sequence running `order[position]`, saved by hand
+    fn saved(order: &[usize], position: usize, index: usize) -> RunnerState {
+        let running = NodeState::new()
+            .with_child(index, NodeState::new().with_data(&1usize).unwrap())
+            .with_data(&(order, position))
+            .unwrap();
+        RunnerState {
+            running: Some(running),
+        }
+    }
+
+    #[test]
+    fn test_shuffled_sequence() {
+        let tree = (0..8)
+            .map(|id| After::new(2, true).with_id(id).arc())
+            .collect::<ShuffledSequence<_>>()
+            .arc();
+        let (res, log) = run_seeded(tree.clone(), 0);
+        check!(res);
+        // Every child runs to completion before the next
+        check!(log.len() == 16);
+        check!(log.chunks(2).all(|ticks| ticks[0] == ticks[1]));
+        let mut order = log.iter().step_by(2).copied().collect::<Vec<_>>();
+        check!(order != (0..8).collect::<Vec<_>>());
+        order.sort();
+        check!(order == (0..8).collect::<Vec<_>>());
+
+        // The same seed gives the same run
+        check!(run_seeded(tree, 0).1 == log);
+    }
+
+    #[test]
+    fn test_random_selector() {
+        let tree = (0..8)
+            .map(|id| After::new(1, id == 3).with_id(id).arc())
+            .collect::<RandomSelector<_>>()
+            .arc();
+        let (res, log) = run_seeded(tree, 1);
+        check!(res);
+        // Stops at the only child that succeeds
+        check!(log.last() == Some(&3));
+        check!(log.iter().filter(|&&id| id == 3).count() == 1);
+    }
+
+    #[test]
+    fn test_weighted_choice() {
+        let tree = [(0.0, 0), (1.0, 1), (3.0, 2)]
+            .into_iter()
+            .map(|(weight, id)| (weight, After::new(2, true).with_id(id).arc()))
+            .collect::<WeightedChoice<_>>()
+            .arc();
+        let mut picks = [0; 3];
+        for seed in 0..100 {
+            let (res, log) = run_seeded(tree.clone(), seed);
+            check!(res);
+            // Only one child runs
+            check!(log.len() == 2);
+            check!(log[0] == log[1]);
+            picks[log[0]] += 1;
+        }
+        check!(picks[0] == 0);
+        check!(picks[2] > picks[1]);
+
+        let tree = WeightedChoice::<Context>::from_iter([(0.0, tree)]).arc();
+        check!(!run_seeded(tree, 0).0);
+    }
+
+    #[test]
+    fn test_restore_shuffled() {
+        let tree = (0..3)
+            .map(|id| After::succeed(2).with_id(id).arc())
+            .collect::<ShuffledSequence<_>>()
+            .arc();
+        let mut context = Context::seeded(0);
+        let mut runner =
+            BehaviorRunner::restore(tree.clone(), &saved(&[2, 0, 1], 0, 2), &context).unwrap();
+        while runner.proceed(&mut context).is_none() {}
+        check!(context.log == [2, 0, 0, 1, 1]);
+
+        // Orders that skip or repeat children, or positions past them, aren't restored
+        for (order, position, index) in [
+            (&[0, 7, 1][..], 0, 0),
+            (&[0, 0, 1], 0, 0),
+            (&[0, 1], 0, 0),
+            (&[0, 1, 2], 3, 2),
+        ] {
+            let restored = BehaviorRunner::restore(
+                tree.clone(),
+                &saved(order, position, index),
+                &Context::seeded(0),
+            );
+            check!(matches!(restored, Err(SnapshotError::Mismatch(_))));
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use crate::inspect::tick_child;
+use crate::snapshot::{mismatch, restore_only_child, NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Ticks `seq` from the start, moving on to the next child while children complete
+/// with `proceed`
+///
+/// `running` is the child that was running last tick, which is resumed instead of
+/// being ticked anew. If an earlier child takes over, it is halted.
+fn react<B: 'static>(
+    seq: &Arc<[BehaviorArc<B>]>,
+    mut running: Option<(usize, &BehaviorArc<B>)>,
+    proceed: bool,
+    blackboard: &mut B,
+) -> Result<bool, (usize, BehaviorArc<B>)> {
+    for (idx, sub) in seq.iter().enumerate() {
+        let result = match running {
+            Some((index, resume)) if index == idx => {
+                running = None;
+                tick_child(idx, resume.as_ref(), blackboard)
+            }
+            _ => tick_child(idx, sub.as_ref(), blackboard),
+        };
+        let success = match result {
+            NodeResult::Success => true,
+            NodeResult::Failure => false,
+            NodeResult::Running(resume) => {
+                if let Some((_, preempted)) = running {
+                    preempted.halt(blackboard);
+                }
+                return Err((idx, resume));
+            }
+        };
+        if success != proceed {
+            if let Some((_, preempted)) = running {
+                preempted.halt(blackboard);
+            }
+            return Ok(success);
+        }
+    }
+    Ok(proceed)
+}
+
+/// A [`Sequence`](super::Sequence) that checks earlier children again every tick
+///
+/// If a child before the running one fails, the running child is halted
+/// and the sequence fails.
+pub struct ReactiveSequence<B> {
+    pub(crate) sub: Arc<[BehaviorArc<B>]>,
+}
+
+impl<B> std::fmt::Debug for ReactiveSequence<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("ReactiveSequence<{:p}>", self.sub.as_ref()))
+            .field("sub", &self.sub)
+            .finish()
+    }
+}
+
+impl<B, I: Into<BehaviorArc<B>>> FromIterator<I> for ReactiveSequence<B> {
+    fn from_iter<T: IntoIterator<Item = I>>(iter: T) -> Self {
+        Self {
+            sub: Arc::from(iter.into_iter().map(Into::into).collect::<Vec<_>>()),
+        }
+    }
+}
+
+impl<B: 'static> ReactiveSequence<B> {
+    fn step(
+        seq: &Arc<[BehaviorArc<B>]>,
+        running: Option<(usize, &BehaviorArc<B>)>,
+        blackboard: &mut B,
+    ) -> NodeResult<B> {
+        match react(seq, running, true, blackboard) {
+            Ok(true) => NodeResult::Success,
+            Ok(false) => NodeResult::Failure,
+            Err((index, resume)) => NodeResult::Running(
+                ReactiveSequenceResume {
+                    seq: seq.clone(),
+                    resume,
+                    index,
+                }
+                .arc(),
+            ),
+        }
+    }
+}
+
+impl<B: 'static> BehaviorNode<B> for ReactiveSequence<B> {
+    fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
+        Self::step(&self.sub, None, blackboard)
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.sub.to_vec()
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let (index, resume) =
+            restore_only_child(self, &self.sub, state, context)?.ok_or_else(|| mismatch(self))?;
+        Ok(ReactiveSequenceResume {
+            seq: self.sub.clone(),
+            resume,
+            index,
+        }
+        .arc())
+    }
+}
+
+pub(crate) struct ReactiveSequenceResume<B> {
+    pub(crate) seq: Arc<[BehaviorArc<B>]>,
+    pub(crate) resume: BehaviorArc<B>,
+    pub(crate) index: usize,
+}
+
+impl<B> std::fmt::Debug for ReactiveSequenceResume<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("ReactiveSequenceResume<{:p}>", self.seq.as_ref()))
+            .field("resume", &self.resume)
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B: 'static> BehaviorNode<B> for ReactiveSequenceResume<B> {
+    fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
+        ReactiveSequence::step(&self.seq, Some((self.index, &self.resume)), blackboard)
+    }
+
+    fn halt(&self, blackboard: &mut B) {
+        self.resume.halt(blackboard);
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        "ReactiveSequence".into()
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.seq.to_vec()
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        Ok(NodeState::new().with_child(self.index, self.resume.snapshot(context)?))
+    }
+}
+
+/// A [`Selector`](super::Selector) that checks earlier children again every tick
+///
+/// If a child before the running one succeeds or starts running, the running
+/// child is halted in its favor.
+pub struct ReactiveSelector<B> {
+    pub(crate) sub: Arc<[BehaviorArc<B>]>,
+}
+
+impl<B> std::fmt::Debug for ReactiveSelector<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("ReactiveSelector<{:p}>", self.sub.as_ref()))
+            .field("sub", &self.sub)
+            .finish()
+    }
+}
+
+impl<B, I: Into<BehaviorArc<B>>> FromIterator<I> for ReactiveSelector<B> {
+    fn from_iter<T: IntoIterator<Item = I>>(iter: T) -> Self {
+        Self {
+            sub: Arc::from(iter.into_iter().map(Into::into).collect::<Vec<_>>()),
+        }
+    }
+}
+
+impl<B: 'static> ReactiveSelector<B> {
+    fn step(
+        seq: &Arc<[BehaviorArc<B>]>,
+        running: Option<(usize, &BehaviorArc<B>)>,
+        blackboard: &mut B,
+    ) -> NodeResult<B> {
+        match react(seq, running, false, blackboard) {
+            Ok(true) => NodeResult::Success,
+            Ok(false) => NodeResult::Failure,
+            Err((index, resume)) => NodeResult::Running(
+                ReactiveSelectorResume {
+                    seq: seq.clone(),
+                    resume,
+                    index,
+                }
+                .arc(),
+            ),
+        }
+    }
+}
+
+impl<B: 'static> BehaviorNode<B> for ReactiveSelector<B> {
+    fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
+        Self::step(&self.sub, None, blackboard)
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.sub.to_vec()
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let (index, resume) =
+            restore_only_child(self, &self.sub, state, context)?.ok_or_else(|| mismatch(self))?;
+        Ok(ReactiveSelectorResume {
+            seq: self.sub.clone(),
+            resume,
+            index,
+        }
+        .arc())
+    }
+}
+
+pub(crate) struct ReactiveSelectorResume<B> {
+    pub(crate) seq: Arc<[BehaviorArc<B>]>,
+    pub(crate) resume: BehaviorArc<B>,
+    pub(crate) index: usize,
+}
+
+impl<B> std::fmt::Debug for ReactiveSelectorResume<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("ReactiveSelectorResume<{:p}>", self.seq.as_ref()))
+            .field("resume", &self.resume)
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B: 'static> BehaviorNode<B> for ReactiveSelectorResume<B> {
+    fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
+        ReactiveSelector::step(&self.seq, Some((self.index, &self.resume)), blackboard)
+    }
+
+    fn halt(&self, blackboard: &mut B) {
+        self.resume.halt(blackboard);
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        "ReactiveSelector".into()
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.seq.to_vec()
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        Ok(NodeState::new().with_child(self.index, self.resume.snapshot(context)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReactiveSelector, ReactiveSequence};
+    use crate::{BehaviorNode, BehaviorRunner, NodeResult};
+    use assert2::check;
+
+    #[derive(Debug, Default)]
+    struct World {
+        /// Whether the player can be seen
+        spotted: bool,
+        /// Every tick of a patrol or chase
+        log: Vec<&'static str>,
+        halted: Vec<&'static str>,
+    }
+
+    #[derive(Debug)]
+    struct Spotted;
+
+    impl BehaviorNode<World> for Spotted {
+        fn tick(&self, world: &mut World) -> NodeResult<World> {
+            if world.spotted {
+                NodeResult::Success
+            } else {
+                NodeResult::Failure
+            }
+        }
+    }
+
+    /// Runs forever, logging `name`
+    #[derive(Debug)]
+    struct Forever(&'static str);
+
+    impl BehaviorNode<World> for Forever {
+        fn tick(&self, world: &mut World) -> NodeResult<World> {
+            world.log.push(self.0);
+            NodeResult::Running(Forever(self.0).arc())
+        }
+
+        fn halt(&self, world: &mut World) {
+            world.halted.push(self.0);
+        }
+    }
+
+    #[test]
+    fn test_reactive_sequence() {
+        let mut world = World {
+            spotted: true,
+            ..Default::default()
+        };
+        let tree = [Spotted.arc(), Forever("chase").arc()]
+            .into_iter()
+            .collect::<ReactiveSequence<_>>()
+            .arc();
+        let mut runner = BehaviorRunner::new(tree);
+
+        check!(runner.proceed(&mut world).is_none());
+        check!(runner.proceed(&mut world).is_none());
+        check!(world.log == ["chase", "chase"]);
+
+        // The condition is checked again, and stops the chase
+        world.spotted = false;
+        check!(runner.proceed(&mut world) == Some(false));
+        check!(world.log.len() == 2);
+        check!(world.halted == ["chase"]);
+    }
+
+    #[test]
+    fn test_reactive_selector_preempts() {
+        let mut world = World::default();
+        let chase = [Spotted.arc(), Forever("chase").arc()]
+            .into_iter()
+            .collect::<ReactiveSequence<_>>()
+            .arc();
+        let tree = [chase, Forever("patrol").arc()]
+            .into_iter()
+            .collect::<ReactiveSelector<_>>()
+            .arc();
+        let mut runner = BehaviorRunner::new(tree);
+
+        check!(runner.proceed(&mut world).is_none());
+        check!(runner.proceed(&mut world).is_none());
+        check!(world.log == ["patrol", "patrol"]);
+
+        // Spotting the player stops the patrol
+        world.spotted = true;
+        check!(runner.proceed(&mut world).is_none());
+        check!(world.log.last() == Some(&"chase"));
+        check!(world.halted == ["patrol"]);
+
+        // Losing them again stops the chase
+        world.spotted = false;
+        check!(runner.proceed(&mut world).is_none());
+        check!(world.log.last() == Some(&"patrol"));
+        check!(world.halted == ["patrol", "chase"]);
+
+        // Interrupting the whole tree halts what is running
+        runner.halt(&mut world);
+        check!(world.halted == ["patrol", "chase", "patrol"]);
+        check!(!runner.is_running());
+    }
+}
@@ -0,0 +1,343 @@
+use crate::inspect::tick_child;
+use crate::snapshot::{snapshot_decorated, NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Repeats its child infintely
+pub struct Repeated<B> {
+    resume: Option<BehaviorArc<B>>,
+    child: BehaviorArc<B>,
+}
+
+impl<B> Debug for Repeated<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Repeated")
+            .field("child", &self.child)
+            .finish()
+    }
+}
+
+impl<B> Repeated<B> {
+    pub fn new(child: BehaviorArc<B>) -> Self {
+        Self {
+            child,
+            resume: None,
+        }
+    }
+}
+
+impl<B: 'static> BehaviorNode<B> for Repeated<B> {
+    fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
+        if let Some(resume) = self.resume.as_ref() {
+            if let NodeResult::Running(resume) = tick_child(0, resume.as_ref(), blackboard) {
+                return NodeResult::Running(
+                    Self {
+                        resume: Some(resume),
+                        child: self.child.clone(),
+                    }
+                    .arc(),
+                );
+            }
+        }
+        if let NodeResult::Running(resume) = tick_child(0, self.child.as_ref(), blackboard) {
+            return NodeResult::Running(
+                Self {
+                    resume: Some(resume),
+                    child: self.child.clone(),
+                }
+                .arc(),
+            );
+        }
+
+        // Restart, cuz we never end
+        NodeResult::Running(Arc::new(Self {
+            child: self.child.clone(),
+            resume: None,
+        }))
+    }
+
+    fn halt(&self, blackboard: &mut B) {
+        if let Some(resume) = self.resume.as_ref() {
+            resume.halt(blackboard);
+        }
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        vec![self.child.clone()]
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        snapshot_decorated(self.resume.as_ref(), context)
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let resume = state
+            .child(0)
+            .map(|state| self.child.restore(state, context))
+            .transpose()?;
+        Ok(Self {
+            resume,
+            child: self.child.clone(),
+        }
+        .arc())
+    }
+}
+
+/// Repeats its child a set number of times
+///
+/// Both success and failure of the child count as a completed run.
+pub struct LimitedRepeated<B> {
+    resume: Option<BehaviorArc<B>>,
+    child: BehaviorArc<B>,
+    limit: usize,
+    completed: usize,
+}
+
+impl<B> Debug for LimitedRepeated<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LimitedRepeated")
+            .field("child", &self.child)
+            .field("limit", &self.limit)
+            .field("completed", &self.completed)
+            .finish()
+    }
+}
+
+impl<B> LimitedRepeated<B> {
+    pub fn new(child: BehaviorArc<B>, limit: usize) -> Self {
+        Self {
+            child,
+            limit,
+            completed: 0,
+            resume: None,
+        }
+    }
+
+    fn proceed(&self, resume: Option<BehaviorArc<B>>, completed: usize) -> Self {
+        Self {
+            resume,
+            child: self.child.clone(),
+            limit: self.limit,
+            completed,
+        }
+    }
+}
+
+impl<B: 'static> BehaviorNode<B> for LimitedRepeated<B> {
+    fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
+        let mut completed = self.completed;
+        if let Some(resume) = self.resume.as_ref() {
+            if let NodeResult::Running(resume) = tick_child(0, resume.as_ref(), blackboard) {
+                return NodeResult::Running(self.proceed(Some(resume), completed).arc());
+            }
+            completed += 1;
+        }
+        if completed >= self.limit {
+            return NodeResult::Success;
+        }
+        if let NodeResult::Running(resume) = tick_child(0, self.child.as_ref(), blackboard) {
+            return NodeResult::Running(self.proceed(Some(resume), completed).arc());
+        }
+        completed += 1;
+
+        if completed >= self.limit {
+            NodeResult::Success
+        } else {
+            NodeResult::Running(self.proceed(None, completed).arc())
+        }
+    }
+
+    fn halt(&self, blackboard: &mut B) {
+        if let Some(resume) = self.resume.as_ref() {
+            resume.halt(blackboard);
+        }
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        format!("LimitedRepeated({})", self.limit).into()
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        vec![self.child.clone()]
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        snapshot_decorated(self.resume.as_ref(), context)?.with_data(&self.completed)
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let resume = state
+            .child(0)
+            .map(|state| self.child.restore(state, context))
+            .transpose()?;
+        Ok(self.proceed(resume, state.data()?).arc())
+    }
+}
+
+/// Repeats its child until its child fails
+///
+/// Succeeds once the child fails.
+pub struct RepeatedUntilFailure<B> {
+    resume: Option<BehaviorArc<B>>,
+    child: BehaviorArc<B>,
+}
+
+impl<B> Debug for RepeatedUntilFailure<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RepeatedUntilFailure")
+            .field("child", &self.child)
+            .finish()
+    }
+}
+
+impl<B> RepeatedUntilFailure<B> {
+    pub fn new(child: BehaviorArc<B>) -> Self {
+        Self {
+            child,
+            resume: None,
+        }
+    }
+
+    fn proceed(&self, resume: Option<BehaviorArc<B>>) -> Self {
+        Self {
+            resume,
+            child: self.child.clone(),
+        }
+    }
+}
+
+impl<B: 'static> BehaviorNode<B> for RepeatedUntilFailure<B> {
+    fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
+        if let Some(resume) = self.resume.as_ref() {
+            match tick_child(0, resume.as_ref(), blackboard) {
+                NodeResult::Running(resume) => {
+                    return NodeResult::Running(self.proceed(Some(resume)).arc())
+                }
+                NodeResult::Failure => return NodeResult::Success,
+                NodeResult::Success => {}
+            }
+        }
+        match tick_child(0, self.child.as_ref(), blackboard) {
+            NodeResult::Running(resume) => NodeResult::Running(self.proceed(Some(resume)).arc()),
+            NodeResult::Failure => NodeResult::Success,
+            // Go again next tick
+            NodeResult::Success => NodeResult::Running(self.proceed(None).arc()),
+        }
+    }
+
+    fn halt(&self, blackboard: &mut B) {
+        if let Some(resume) = self.resume.as_ref() {
+            resume.halt(blackboard);
+        }
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        vec![self.child.clone()]
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        snapshot_decorated(self.resume.as_ref(), context)
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let resume = state
+            .child(0)
+            .map(|state| self.child.restore(state, context))
+            .transpose()?;
+        Ok(self.proceed(resume).arc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LimitedRepeated, Repeated, RepeatedUntilFailure};
+    use crate::test_support::{run_for, After};
+    use crate::BehaviorNode;
+    use assert2::check;
+
+    #[test]
+    fn test_repeated_never_finishes() {
+        let mut count = 0;
+        let (res, _) = run_for(Repeated::new(After::succeed(3).arc()).arc(), &mut count, 30);
+        check!(res.is_none());
+        check!(count >= 30);
+    }
+
+    #[test]
+    fn test_limited_repeated() {
+        let mut count = 0;
+        let (res, ticks) = run_for(
+            LimitedRepeated::new(After::succeed(1).arc(), 4).arc(),
+            &mut count,
+            100,
+        );
+        check!(res == Some(true));
+        check!(count == 4);
+        check!(ticks <= 4);
+    }
+
+    #[test]
+    fn test_limited_repeated_resumes() {
+        let mut count = 0;
+        let (res, ticks) = run_for(
+            LimitedRepeated::new(After::succeed(3).arc(), 3).arc(),
+            &mut count,
+            100,
+        );
+        check!(res == Some(true));
+        // Each run takes 3 ticks, and resuming doesn't restart the child
+        check!(count == 9);
+        check!(ticks <= 9);
+    }
+
+    #[test]
+    fn test_limited_repeated_counts_failure() {
+        let mut count = 0;
+        let (res, _) = run_for(
+            LimitedRepeated::new(After::fail(1).arc(), 2).arc(),
+            &mut count,
+            100,
+        );
+        check!(res == Some(true));
+        check!(count == 2);
+    }
+
+    #[test]
+    fn test_limited_repeated_zero() {
+        let mut count = 0;
+        let (res, ticks) = run_for(
+            LimitedRepeated::new(After::succeed(1).arc(), 0).arc(),
+            &mut count,
+            100,
+        );
+        check!(res == Some(true));
+        check!(ticks == 1);
+        check!(count == 0);
+    }
+
+    #[test]
+    fn test_repeated_until_failure() {
+        // Successes repeat the child...
+        let mut count = 0;
+        let (res, _) = run_for(
+            RepeatedUntilFailure::new(After::succeed(2).arc()).arc(),
+            &mut count,
+            10,
+        );
+        check!(res.is_none());
+        check!(count >= 10);
+
+        // ...until it fails
+        let mut count = 0;
+        let (res, ticks) = run_for(
+            RepeatedUntilFailure::new(After::fail(2).arc()).arc(),
+            &mut count,
+            100,
+        );
+        check!(res == Some(true));
+        check!(count == 2);
+        check!(ticks == 2);
+    }
+}
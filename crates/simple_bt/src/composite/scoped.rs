@@ -0,0 +1,173 @@
+use crate::blackboard::HasBlackboard;
+use crate::inspect::tick_child;
+use crate::snapshot::{restore_decorated, NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Runs its child in its own [`Blackboard`](crate::blackboard::Blackboard) scope
+///
+/// Values the child sets are kept while it is running, and dropped once it completes.
+/// Each node has a scope of its own, even if another node in the tree shares its name.
+pub struct Scoped<B> {
+    name: Cow<'static, str>,
+    child: BehaviorArc<B>,
+    /// Shared with continuations, so they enter the same scope as the node
+    owner: Arc<()>,
+}
+
+impl<B> std::fmt::Debug for Scoped<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scoped")
+            .field("name", &self.name)
+            .field("child", &self.child)
+            .finish()
+    }
+}
+
+impl<B> Scoped<B> {
+    pub fn new(name: impl Into<Cow<'static, str>>, child: BehaviorArc<B>) -> Self {
+        Self {
+            name: name.into(),
+            child,
+            owner: Arc::new(()),
+        }
+    }
+
+    /// Identifies the scope of this node and its continuations on the blackboard
+    fn owner(&self) -> usize {
+        Arc::as_ptr(&self.owner) as usize
+    }
+}
+
+impl<B: HasBlackboard + 'static> BehaviorNode<B> for Scoped<B> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        context
+            .blackboard_mut()
+            .enter_owned_scope(self.owner(), self.name.clone());
+        let result = tick_child(0, self.child.as_ref(), context);
+        context
+            .blackboard_mut()
+            .exit_scope(matches!(result, NodeResult::Running(_)));
+        match result {
+            NodeResult::Running(resume) => NodeResult::Running(
+                Self {
+                    name: self.name.clone(),
+                    child: resume,
+                    owner: self.owner.clone(),
+                }
+                .arc(),
+            ),
+            result => result,
+        }
+    }
+
+    fn halt(&self, context: &mut B) {
+        context
+            .blackboard_mut()
+            .enter_owned_scope(self.owner(), self.name.clone());
+        self.child.halt(context);
+        context.blackboard_mut().exit_scope(false);
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        format!("Scoped({})", self.name).into()
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        vec![self.child.clone()]
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        Ok(NodeState::new().with_child(0, self.child.snapshot(context)?))
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        Ok(Self {
+            name: self.name.clone(),
+            child: restore_decorated(self, &self.child, state, context)?,
+            owner: self.owner.clone(),
+        }
+        .arc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Scoped;
+    use crate::blackboard::{Blackboard, BlackboardKey};
+    use crate::composite::Parallel;
+    use crate::{BehaviorNode, BehaviorRunner, NodeResult};
+    use assert2::check;
+
+    const STEPS: BlackboardKey<usize> = BlackboardKey::new("steps");
+
+    /// Counts up [`STEPS`] until it reaches `goal`
+    #[derive(Debug)]
+    struct Step {
+        goal: usize,
+    }
+
+    impl BehaviorNode<Blackboard> for Step {
+        fn tick(&self, blackboard: &mut Blackboard) -> NodeResult<Blackboard> {
+            let steps = blackboard.get_or_default(&STEPS) + 1;
+            blackboard.set(&STEPS, steps);
+            if steps >= self.goal {
+                NodeResult::Success
+            } else {
+                NodeResult::Running(Self { goal: self.goal }.arc())
+            }
+        }
+    }
+
+    #[test]
+    fn test_scoped() {
+        let mut blackboard = Blackboard::new();
+        let mut runner = BehaviorRunner::new(Scoped::new("walk", Step { goal: 3 }.arc()).arc());
+
+        // Values set in the scope don't leak out of it...
+        check!(runner.proceed(&mut blackboard).is_none());
+        check!(!blackboard.contains(&STEPS));
+
+        // ...but are kept between ticks
+        check!(runner.proceed(&mut blackboard).is_none());
+        check!(runner.proceed(&mut blackboard) == Some(true));
+        check!(!blackboard.contains(&STEPS));
+
+        // and forgets them once done
+        blackboard.enter_scope("walk");
+        check!(!blackboard.contains(&STEPS));
+    }
+
+    #[test]
+    fn test_same_names_in_parallel() {
+        let mut blackboard = Blackboard::new();
+        let tree = [2, 3]
+            .map(|goal| Scoped::new("walk", Step { goal }.arc()).arc())
+            .into_iter()
+            .collect::<Parallel<_>>()
+            .arc();
+        let mut runner = BehaviorRunner::new(tree);
+
+        // Each branch counts its own steps
+        check!(runner.proceed(&mut blackboard).is_none());
+        check!(blackboard.suspended_scopes() == 2);
+        check!(runner.proceed(&mut blackboard).is_none());
+        check!(blackboard.suspended_scopes() == 1);
+        check!(runner.proceed(&mut blackboard) == Some(true));
+        check!(blackboard.suspended_scopes() == 0);
+    }
+
+    #[test]
+    fn test_halt_closes_scopes() {
+        let mut blackboard = Blackboard::new();
+        let inner = Scoped::new("inner", Step { goal: 3 }.arc()).arc();
+        let mut runner = BehaviorRunner::new(Scoped::new("outer", inner).arc());
+        check!(runner.proceed(&mut blackboard).is_none());
+        check!(blackboard.suspended_scopes() == 2);
+
+        runner.halt(&mut blackboard);
+        check!(blackboard.suspended_scopes() == 0);
+        check!(blackboard.scope_path().is_empty());
+    }
+}
@@ -1,4 +1,7 @@
-use crate::simple_bt::{BehaviorArc, BehaviorNode, NodeResult};
+use crate::inspect::tick_child;
+use crate::snapshot::{mismatch, restore_only_child, NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+use std::borrow::Cow;
 use std::sync::Arc;
 
 pub struct Selector<B> {
@@ -34,7 +37,7 @@ impl<B: 'static> Selector<B> {
 impl<B: 'static> BehaviorNode<B> for Selector<B> {
     fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
         for (idx, sub) in self.sub.iter().enumerate() {
-            match sub.tick(blackboard) {
+            match tick_child(idx, sub.as_ref(), blackboard) {
                 NodeResult::Failure => {}
                 NodeResult::Success => return NodeResult::Success,
                 NodeResult::Running(resume) => {
@@ -44,6 +47,16 @@ impl<B: 'static> BehaviorNode<B> for Selector<B> {
         }
         NodeResult::Failure
     }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.sub.to_vec()
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let (index, resume) =
+            restore_only_child(self, &self.sub, state, context)?.ok_or_else(|| mismatch(self))?;
+        Ok(Self::resume(self.sub.clone(), index, resume))
+    }
 }
 
 pub(crate) struct SelectorResume<B> {
@@ -64,15 +77,15 @@ impl<B> std::fmt::Debug for SelectorResume<B> {
 impl<B: 'static> BehaviorNode<B> for SelectorResume<B> {
     fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
         // Tick the node we want to resume on
-        match self.resume.tick(blackboard) {
+        match tick_child(self.index, self.resume.as_ref(), blackboard) {
             NodeResult::Failure => {}
             NodeResult::Success => return NodeResult::Success,
             NodeResult::Running(resume) => {
                 return NodeResult::Running(Selector::resume(self.seq.clone(), self.index, resume))
             }
         }
-        for (idx, sub) in self.seq.iter().enumerate().skip(self.index) {
-            match sub.tick(blackboard) {
+        for (idx, sub) in self.seq.iter().enumerate().skip(self.index + 1) {
+            match tick_child(idx, sub.as_ref(), blackboard) {
                 NodeResult::Failure => {}
                 NodeResult::Success => return NodeResult::Success,
                 NodeResult::Running(resume) => {
@@ -82,7 +95,64 @@ impl<B: 'static> BehaviorNode<B> for SelectorResume<B> {
         }
         NodeResult::Failure
     }
+
+    fn halt(&self, blackboard: &mut B) {
+        self.resume.halt(blackboard);
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        "Selector".into()
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.seq.to_vec()
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        Ok(NodeState::new().with_child(self.index, self.resume.snapshot(context)?))
+    }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::Selector;
+    use crate::test_support::{run, After};
+    use crate::BehaviorNode;
+    use assert2::check;
+
+    fn select(children: &[After]) -> (bool, Vec<usize>) {
+        let tree = children
+            .iter()
+            .map(|child| child.clone().arc())
+            .collect::<Selector<_>>()
+            .arc();
+        let mut log = vec![];
+        (run(tree, &mut log), log)
+    }
+
+    #[test]
+    fn test_selector_first_success() {
+        let (res, log) = select(&[
+            After::fail(1),
+            After::succeed(2).with_id(1),
+            After::succeed(1).with_id(2),
+        ]);
+        check!(res);
+        // The running child is resumed, and later children are never ticked
+        check!(log == [0, 1, 1]);
+    }
+
+    #[test]
+    fn test_selector_all_fail() {
+        let (res, log) = select(&[After::fail(2), After::fail(1).with_id(1)]);
+        check!(!res);
+        check!(log == [0, 0, 1]);
+    }
+
+    #[test]
+    fn test_selector_empty() {
+        let (res, log) = select(&[]);
+        check!(!res);
+        check!(log.is_empty());
+    }
+}
@@ -1,4 +1,7 @@
-use crate::simple_bt::{BehaviorArc, BehaviorNode, NodeResult};
+use crate::inspect::tick_child;
+use crate::snapshot::{mismatch, restore_only_child, NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+use std::borrow::Cow;
 use std::sync::Arc;
 
 pub struct Sequence<B> {
@@ -34,7 +37,7 @@ impl<B: 'static> Sequence<B> {
 impl<B: 'static> BehaviorNode<B> for Sequence<B> {
     fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
         for (idx, sub) in self.sub.iter().enumerate() {
-            match sub.tick(blackboard) {
+            match tick_child(idx, sub.as_ref(), blackboard) {
                 NodeResult::Success => {}
                 NodeResult::Failure => return NodeResult::Failure,
                 NodeResult::Running(resume) => {
@@ -44,6 +47,16 @@ impl<B: 'static> BehaviorNode<B> for Sequence<B> {
         }
         NodeResult::Success
     }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.sub.to_vec()
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let (index, resume) =
+            restore_only_child(self, &self.sub, state, context)?.ok_or_else(|| mismatch(self))?;
+        Ok(Self::resume(self.sub.clone(), index, resume))
+    }
 }
 
 pub(crate) struct SequenceResume<B> {
@@ -64,15 +77,15 @@ impl<B> std::fmt::Debug for SequenceResume<B> {
 impl<B: 'static> BehaviorNode<B> for SequenceResume<B> {
     fn tick(&self, blackboard: &mut B) -> NodeResult<B> {
         // Tick the node we want to resume on
-        match self.resume.tick(blackboard) {
+        match tick_child(self.index, self.resume.as_ref(), blackboard) {
             NodeResult::Success => {}
             NodeResult::Failure => return NodeResult::Failure,
             NodeResult::Running(resume) => {
                 return NodeResult::Running(Sequence::resume(self.seq.clone(), self.index, resume))
             }
         }
-        for (idx, sub) in self.seq.iter().enumerate().skip(self.index) {
-            match sub.tick(blackboard) {
+        for (idx, sub) in self.seq.iter().enumerate().skip(self.index + 1) {
+            match tick_child(idx, sub.as_ref(), blackboard) {
                 NodeResult::Success => {}
                 NodeResult::Failure => return NodeResult::Failure,
                 NodeResult::Running(resume) => {
@@ -82,28 +95,43 @@ impl<B: 'static> BehaviorNode<B> for SequenceResume<B> {
         }
         NodeResult::Success
     }
+
+    fn halt(&self, blackboard: &mut B) {
+        self.resume.halt(blackboard);
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        "Sequence".into()
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.seq.to_vec()
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        Ok(NodeState::new().with_child(self.index, self.resume.snapshot(context)?))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{BehaviorNode, NodeResult, Sequence};
-    use crate::simple_bt::BehaviorRunner;
+    use crate::BehaviorRunner;
     use assert2::check;
-    use bevy::prelude::*;
 
     #[derive(Debug)]
     struct MoveTo {
         part: f32,
-        goal: Vec2,
+        goal: f32,
     }
 
-    impl BehaviorNode<Vec2> for MoveTo {
-        fn tick(&self, position: &mut Vec2) -> NodeResult<Vec2> {
+    impl BehaviorNode<f32> for MoveTo {
+        fn tick(&self, position: &mut f32) -> NodeResult<f32> {
             let movement = (self.goal - *position) * self.part.recip();
             *position += movement;
 
             const ERROR: f32 = 0.001;
-            if (self.goal - *position).length() < ERROR {
+            if (self.goal - *position).abs() < ERROR {
                 NodeResult::Success
             } else {
                 NodeResult::Running(
@@ -122,12 +150,12 @@ mod tests {
         let tree = [
             MoveTo {
                 part: 5.0,
-                goal: Vec2::ZERO,
+                goal: 0.0,
             }
             .arc(),
             MoveTo {
                 part: 5.0,
-                goal: Vec2::splat(5.0),
+                goal: 5.0,
             }
             .arc(),
         ]
@@ -136,7 +164,7 @@ mod tests {
         .arc();
 
         // Set some position
-        let mut position = Vec2::X * 5.0;
+        let mut position = 5.0;
 
         // If a position is set, we will execute the action
         {
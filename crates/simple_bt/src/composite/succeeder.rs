@@ -0,0 +1,87 @@
+use crate::inspect::tick_child;
+use crate::snapshot::{mismatch, restore_decorated, NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+
+/// Always succeedes.
+pub struct Succeeder<B> {
+    child: Option<BehaviorArc<B>>,
+}
+
+impl<B> std::fmt::Debug for Succeeder<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Succeeder")
+            .field("child", &self.child)
+            .finish()
+    }
+}
+
+impl<B> Default for Succeeder<B> {
+    fn default() -> Self {
+        Self { child: None }
+    }
+}
+
+impl<B> Succeeder<B> {
+    pub fn new(child: BehaviorArc<B>) -> Self {
+        Self { child: Some(child) }
+    }
+}
+
+impl<B: 'static> BehaviorNode<B> for Succeeder<B> {
+    fn tick(&self, blackboard: &mut B) -> crate::NodeResult<B> {
+        if let Some(child) = self.child.as_ref() {
+            match tick_child(0, child.as_ref(), blackboard) {
+                NodeResult::Failure | NodeResult::Success => NodeResult::Success,
+                NodeResult::Running(resume) => NodeResult::Running(Succeeder::new(resume).arc()),
+            }
+        } else {
+            NodeResult::Success
+        }
+    }
+
+    fn halt(&self, blackboard: &mut B) {
+        if let Some(child) = self.child.as_ref() {
+            child.halt(blackboard);
+        }
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.child.iter().cloned().collect()
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        let child = self.child.as_ref().ok_or_else(|| mismatch(self))?;
+        Ok(NodeState::new().with_child(0, child.snapshot(context)?))
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let child = self.child.as_ref().ok_or_else(|| mismatch(self))?;
+        Ok(Succeeder::new(restore_decorated(self, child, state, context)?).arc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Succeeder;
+    use crate::test_support::After;
+    use crate::{BehaviorNode, BehaviorRunner};
+    use assert2::check;
+
+    #[test]
+    fn test_succeeder_empty() {
+        let mut runner = BehaviorRunner::new(Succeeder::default().arc());
+        check!(runner.proceed(&mut 0) == Some(true));
+    }
+
+    #[test]
+    fn test_succeeder() {
+        let mut count = 0;
+        for result in [true, false] {
+            let tree = Succeeder::new(After::new(2, result).arc()).arc();
+            let mut runner = BehaviorRunner::new(tree);
+            check!(runner.proceed(&mut count).is_none());
+            check!(runner.proceed(&mut count) == Some(true));
+        }
+        check!(count == 4);
+    }
+}
@@ -0,0 +1,461 @@
+use crate::inspect::tick_child;
+use crate::snapshot::{restore_decorated, snapshot_decorated, NodeState, SnapshotError};
+use crate::time::HasTime;
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// How long until `at`, to be saved
+fn remaining(at: Option<Duration>, now: Duration) -> Option<Duration> {
+    at.map(|at| at.saturating_sub(now))
+}
+
+/// Fails its child if it runs for longer than `duration`
+pub struct Timeout<B> {
+    duration: Duration,
+    child: BehaviorArc<B>,
+    /// Set once the child is running
+    deadline: Option<Duration>,
+}
+
+impl<B> std::fmt::Debug for Timeout<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Timeout")
+            .field("duration", &self.duration)
+            .field("child", &self.child)
+            .field("deadline", &self.deadline)
+            .finish()
+    }
+}
+
+impl<B> Timeout<B> {
+    pub fn new(duration: Duration, child: BehaviorArc<B>) -> Self {
+        Self {
+            duration,
+            child,
+            deadline: None,
+        }
+    }
+}
+
+impl<B: HasTime + 'static> BehaviorNode<B> for Timeout<B> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        let now = context.elapsed();
+        let deadline = match self.deadline {
+            Some(deadline) if now >= deadline => {
+                self.child.halt(context);
+                return NodeResult::Failure;
+            }
+            Some(deadline) => deadline,
+            None => now + self.duration,
+        };
+        match tick_child(0, self.child.as_ref(), context) {
+            NodeResult::Running(resume) => NodeResult::Running(
+                Self {
+                    duration: self.duration,
+                    child: resume,
+                    deadline: Some(deadline),
+                }
+                .arc(),
+            ),
+            result => result,
+        }
+    }
+
+    fn halt(&self, context: &mut B) {
+        self.child.halt(context);
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        format!("Timeout({:?})", self.duration).into()
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        vec![self.child.clone()]
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        NodeState::new()
+            .with_child(0, self.child.snapshot(context)?)
+            .with_data(&remaining(self.deadline, context.elapsed()))
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let remaining: Option<Duration> = state.data()?;
+        Ok(Self {
+            duration: self.duration,
+            child: restore_decorated(self, &self.child, state, context)?,
+            deadline: remaining.map(|remaining| context.elapsed() + remaining),
+        }
+        .arc())
+    }
+}
+
+/// Fails without ticking its child until `duration` has passed since the child last ran
+///
+/// The cooldown lives on the node, so runners that share a tree share its cooldowns.
+pub struct Cooldown<B> {
+    duration: Duration,
+    child: BehaviorArc<B>,
+    resume: Option<BehaviorArc<B>>,
+    /// When the child can next be ticked, shared with our continuations
+    ready_at: Arc<Mutex<Option<Duration>>>,
+}
+
+impl<B> std::fmt::Debug for Cooldown<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cooldown")
+            .field("duration", &self.duration)
+            .field("child", &self.child)
+            .field("ready_at", &self.ready_at)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B> Cooldown<B> {
+    pub fn new(duration: Duration, child: BehaviorArc<B>) -> Self {
+        Self {
+            duration,
+            child,
+            resume: None,
+            ready_at: Arc::default(),
+        }
+    }
+
+    fn ready_at(&self) -> MutexGuard<'_, Option<Duration>> {
+        self.ready_at.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// A continuation running `resume`
+    fn resuming(&self, resume: Option<BehaviorArc<B>>) -> Self {
+        Self {
+            duration: self.duration,
+            child: self.child.clone(),
+            resume,
+            ready_at: self.ready_at.clone(),
+        }
+    }
+}
+
+impl<B: HasTime + 'static> BehaviorNode<B> for Cooldown<B> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        let cooling = self
+            .ready_at()
+            .is_some_and(|ready_at| context.elapsed() < ready_at);
+        let result = match self.resume.as_ref() {
+            Some(resume) => tick_child(0, resume.as_ref(), context),
+            None if cooling => return NodeResult::Failure,
+            None => tick_child(0, self.child.as_ref(), context),
+        };
+        match result {
+            NodeResult::Running(resume) => NodeResult::Running(self.resuming(Some(resume)).arc()),
+            result => {
+                *self.ready_at() = Some(context.elapsed() + self.duration);
+                result
+            }
+        }
+    }
+
+    fn halt(&self, context: &mut B) {
+        if let Some(resume) = self.resume.as_ref() {
+            resume.halt(context);
+            *self.ready_at() = Some(context.elapsed() + self.duration);
+        }
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        format!("Cooldown({:?})", self.duration).into()
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        vec![self.child.clone()]
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        let ready_at = *self.ready_at();
+        snapshot_decorated(self.resume.as_ref(), context)?
+            .with_data(&remaining(ready_at, context.elapsed()))
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let remaining: Option<Duration> = state.data()?;
+        let resume = state
+            .child(0)
+            .map(|state| self.child.restore(state, context))
+            .transpose()?;
+        *self.ready_at() = remaining.map(|remaining| context.elapsed() + remaining);
+        Ok(self.resuming(resume).arc())
+    }
+}
+
+/// Waits for `duration` before ticking its child
+pub struct Delay<B> {
+    duration: Duration,
+    child: BehaviorArc<B>,
+    /// Set once we've started waiting
+    until: Option<Duration>,
+    resume: Option<BehaviorArc<B>>,
+}
+
+impl<B> std::fmt::Debug for Delay<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Delay")
+            .field("duration", &self.duration)
+            .field("child", &self.child)
+            .field("until", &self.until)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B> Delay<B> {
+    pub fn new(duration: Duration, child: BehaviorArc<B>) -> Self {
+        Self {
+            duration,
+            child,
+            until: None,
+            resume: None,
+        }
+    }
+}
+
+impl<B: HasTime + 'static> BehaviorNode<B> for Delay<B> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        let now = context.elapsed();
+        let until = self.until.unwrap_or(now + self.duration);
+        let result = match self.resume.as_ref() {
+            Some(resume) => tick_child(0, resume.as_ref(), context),
+            None if now >= until => tick_child(0, self.child.as_ref(), context),
+            None => {
+                return NodeResult::Running(
+                    Self {
+                        duration: self.duration,
+                        child: self.child.clone(),
+                        until: Some(until),
+                        resume: None,
+                    }
+                    .arc(),
+                )
+            }
+        };
+        match result {
+            NodeResult::Running(resume) => NodeResult::Running(
+                Self {
+                    duration: self.duration,
+                    child: self.child.clone(),
+                    until: Some(until),
+                    resume: Some(resume),
+                }
+                .arc(),
+            ),
+            result => result,
+        }
+    }
+
+    fn halt(&self, context: &mut B) {
+        if let Some(resume) = self.resume.as_ref() {
+            resume.halt(context);
+        }
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        format!("Delay({:?})", self.duration).into()
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        vec![self.child.clone()]
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        snapshot_decorated(self.resume.as_ref(), context)?
+            .with_data(&remaining(self.until, context.elapsed()))
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let remaining: Option<Duration> = state.data()?;
+        let resume = state
+            .child(0)
+            .map(|state| self.child.restore(state, context))
+            .transpose()?;
+        Ok(Self {
+            duration: self.duration,
+            child: self.child.clone(),
+            until: remaining.map(|remaining| context.elapsed() + remaining),
+            resume,
+        }
+        .arc())
+    }
+}
+
+/// Runs for `duration`, then succeeds
+#[derive(Debug, Clone)]
+pub struct Wait {
+    duration: Duration,
+    /// Set once we've started waiting
+    until: Option<Duration>,
+}
+
+impl Wait {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            until: None,
+        }
+    }
+}
+
+impl<B: HasTime> BehaviorNode<B> for Wait {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        let now = context.elapsed();
+        let until = self.until.unwrap_or(now + self.duration);
+        if now < until {
+            NodeResult::Running(
+                Self {
+                    duration: self.duration,
+                    until: Some(until),
+                }
+                .arc(),
+            )
+        } else {
+            NodeResult::Success
+        }
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        format!("Wait({:?})", self.duration).into()
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        NodeState::new().with_data(&remaining(self.until, context.elapsed()))
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let remaining: Option<Duration> = state.data()?;
+        Ok(Self {
+            duration: self.duration,
+            until: remaining.map(|remaining| context.elapsed() + remaining),
+        }
+        .arc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cooldown, Delay, Timeout, Wait};
+    use crate::test_support::{After, Record};
+    use crate::time::{HasTime, ManualClock};
+    use crate::{BehaviorNode, BehaviorRunner};
+    use assert2::check;
+    use std::time::Duration;
+
+    #[derive(Debug, Default)]
+    struct Context {
+        clock: ManualClock,
+        ticks: usize,
+        halts: usize,
+    }
+
+    impl HasTime for Context {
+        fn elapsed(&self) -> Duration {
+            self.clock.elapsed()
+        }
+    }
+
+    impl Record for Context {
+        fn ticked(&mut self, _: usize) {
+            self.ticks += 1;
+        }
+
+        fn halted(&mut self, _: usize) {
+            self.halts += 1;
+        }
+    }
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_wait() {
+        let mut context = Context::default();
+        let mut runner = BehaviorRunner::new(Wait::new(SECOND).arc());
+        check!(runner.proceed(&mut context).is_none());
+        context.clock.advance_secs(0.5);
+        check!(runner.proceed(&mut context).is_none());
+        context.clock.advance_secs(0.5);
+        check!(runner.proceed(&mut context) == Some(true));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut context = Context::default();
+        let mut runner = BehaviorRunner::new(Timeout::new(SECOND, After::succeed(3).arc()).arc());
+        check!(runner.proceed(&mut context).is_none());
+        context.clock.advance_secs(0.5);
+        check!(runner.proceed(&mut context).is_none());
+        context.clock.advance_secs(0.5);
+        check!(runner.proceed(&mut context) == Some(false));
+        check!(context.ticks == 2);
+        check!(context.halts == 1);
+
+        // Children that are fast enough are left alone
+        check!(runner.proceed(&mut context).is_none());
+        check!(runner.proceed(&mut context).is_none());
+        check!(runner.proceed(&mut context) == Some(true));
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let mut context = Context::default();
+        let mut runner = BehaviorRunner::new(Cooldown::new(SECOND, After::succeed(2).arc()).arc());
+        check!(runner.proceed(&mut context).is_none());
+        check!(runner.proceed(&mut context) == Some(true));
+
+        // The cooldown starts once the child completes
+        context.clock.advance_secs(0.5);
+        check!(runner.proceed(&mut context) == Some(false));
+        check!(context.ticks == 2);
+        context.clock.advance_secs(0.5);
+        check!(runner.proceed(&mut context).is_none());
+        check!(context.ticks == 3);
+    }
+
+    #[test]
+    fn test_cooldown_snapshot() {
+        let cooldown = Cooldown::new(SECOND, Wait::new(2 * SECOND).arc());
+        let ready_at = cooldown.ready_at.clone();
+        let tree = cooldown.arc();
+        let mut context = Context::default();
+        let mut runner = BehaviorRunner::new(tree.clone());
+        check!(runner.proceed(&mut context).is_none());
+        context.clock.advance_secs(2.0);
+        check!(runner.proceed(&mut context) == Some(true));
+        context.clock.advance_secs(1.0);
+        check!(runner.proceed(&mut context).is_none());
+
+        // The saved cooldown is put back relative to the new clock
+        let state = runner.snapshot(&context).unwrap();
+        *ready_at.lock().unwrap() = None;
+        let mut loaded = Context::default();
+        loaded.clock.advance_secs(10.0);
+        let mut restored = BehaviorRunner::restore(tree, &state, &loaded).unwrap();
+        check!(*ready_at.lock().unwrap() == Some(10 * SECOND));
+        check!(restored.proceed(&mut loaded).is_none());
+        loaded.clock.advance_secs(2.0);
+        check!(restored.proceed(&mut loaded) == Some(true));
+        check!(restored.proceed(&mut loaded) == Some(false));
+    }
+
+    #[test]
+    fn test_delay() {
+        let mut context = Context::default();
+        let mut runner = BehaviorRunner::new(Delay::new(SECOND, After::succeed(2).arc()).arc());
+        check!(runner.proceed(&mut context).is_none());
+        context.clock.advance_secs(0.5);
+        check!(runner.proceed(&mut context).is_none());
+        check!(context.ticks == 0);
+
+        context.clock.advance_secs(0.5);
+        check!(runner.proceed(&mut context).is_none());
+        check!(runner.proceed(&mut context) == Some(true));
+        check!(context.ticks == 2);
+    }
+}
@@ -0,0 +1,324 @@
+use crate::blackboard::{Blackboard, BlackboardKey, HasBlackboard};
+use crate::inspect::tick_child;
+use crate::snapshot::{mismatch, restore_only_child, NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Scores how much an option of a [`UtilitySelector`] wants to run
+pub type Scorer = Arc<dyn Fn(&Blackboard) -> f32 + Send + Sync>;
+
+type UtilityOption<B> = (Scorer, BehaviorArc<B>);
+
+/// Runs the child that scores best, falling back to the next best if it fails
+///
+/// Scores are checked again every tick. A running child is halted if another scores
+/// higher by more than the hysteresis. Children that score zero or less are never picked.
+pub struct UtilitySelector<B> {
+    options: Arc<[UtilityOption<B>]>,
+    hysteresis: f32,
+}
+
+impl<B> std::fmt::Debug for UtilitySelector<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!("UtilitySelector<{:p}>", self.options.as_ref()))
+            .field(
+                "sub",
+                &self.options.iter().map(|(_, sub)| sub).collect::<Vec<_>>(),
+            )
+            .field("hysteresis", &self.hysteresis)
+            .finish()
+    }
+}
+
+impl<B> Default for UtilitySelector<B> {
+    fn default() -> Self {
+        Self {
+            options: Arc::new([]),
+            hysteresis: 0.0,
+        }
+    }
+}
+
+impl<B> UtilitySelector<B> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `child`, scored by `score`
+    pub fn with_option(
+        self,
+        score: impl Fn(&Blackboard) -> f32 + Send + Sync + 'static,
+        child: BehaviorArc<B>,
+    ) -> Self {
+        let mut options = self.options.to_vec();
+        options.push((Arc::new(score), child));
+        Self {
+            options: options.into(),
+            ..self
+        }
+    }
+
+    /// Add `child`, scored by the value of `key`, or zero if it isn't set
+    pub fn with_scored_option(self, key: BlackboardKey<f32>, child: BehaviorArc<B>) -> Self {
+        self.with_option(move |blackboard| blackboard.get_or_default(&key), child)
+    }
+
+    /// How much better another child must score to take over from a running one
+    pub fn with_hysteresis(self, hysteresis: f32) -> Self {
+        Self { hysteresis, ..self }
+    }
+}
+
+impl<B: HasBlackboard + 'static> UtilitySelector<B> {
+    fn scores(options: &[UtilityOption<B>], context: &B) -> Vec<f32> {
+        options
+            .iter()
+            .map(|(score, _)| score(context.blackboard()))
+            .collect()
+    }
+
+    /// Try every child worth picking, best first, except for `skip`
+    fn pick(
+        options: &Arc<[UtilityOption<B>]>,
+        hysteresis: f32,
+        scores: &[f32],
+        skip: Option<usize>,
+        context: &mut B,
+    ) -> NodeResult<B> {
+        let mut order = (0..options.len())
+            .filter(|&idx| Some(idx) != skip && scores[idx] > 0.0)
+            .collect::<Vec<_>>();
+        // Stable, so ties go to the earlier child
+        order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+        for idx in order {
+            match tick_child(idx, options[idx].1.as_ref(), context) {
+                NodeResult::Failure => {}
+                NodeResult::Success => return NodeResult::Success,
+                NodeResult::Running(resume) => {
+                    return NodeResult::Running(
+                        UtilitySelectorResume {
+                            options: options.clone(),
+                            hysteresis,
+                            resume,
+                            index: idx,
+                        }
+                        .arc(),
+                    )
+                }
+            }
+        }
+        NodeResult::Failure
+    }
+}
+
+impl<B: HasBlackboard + 'static> BehaviorNode<B> for UtilitySelector<B> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        let scores = Self::scores(&self.options, context);
+        Self::pick(&self.options, self.hysteresis, &scores, None, context)
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.options.iter().map(|(_, sub)| sub.clone()).collect()
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        let (index, resume) = restore_only_child(self, &self.children(), state, context)?
+            .ok_or_else(|| mismatch(self))?;
+        Ok(UtilitySelectorResume {
+            options: self.options.clone(),
+            hysteresis: self.hysteresis,
+            resume,
+            index,
+        }
+        .arc())
+    }
+}
+
+pub(crate) struct UtilitySelectorResume<B> {
+    options: Arc<[UtilityOption<B>]>,
+    hysteresis: f32,
+    resume: BehaviorArc<B>,
+    index: usize,
+}
+
+impl<B> std::fmt::Debug for UtilitySelectorResume<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!(
+            "UtilitySelectorResume<{:p}>",
+            self.options.as_ref()
+        ))
+        .field("resume", &self.resume)
+        .field("index", &self.index)
+        .finish_non_exhaustive()
+    }
+}
+
+impl<B: HasBlackboard + 'static> BehaviorNode<B> for UtilitySelectorResume<B> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        let scores = UtilitySelector::scores(&self.options, context);
+        let current = scores[self.index];
+        if scores
+            .iter()
+            .any(|&score| score > current + self.hysteresis)
+        {
+            self.resume.halt(context);
+            return UtilitySelector::pick(&self.options, self.hysteresis, &scores, None, context);
+        }
+        match tick_child(self.index, self.resume.as_ref(), context) {
+            NodeResult::Success => NodeResult::Success,
+            NodeResult::Failure => UtilitySelector::pick(
+                &self.options,
+                self.hysteresis,
+                &scores,
+                Some(self.index),
+                context,
+            ),
+            NodeResult::Running(resume) => NodeResult::Running(
+                Self {
+                    options: self.options.clone(),
+                    hysteresis: self.hysteresis,
+                    resume,
+                    index: self.index,
+                }
+                .arc(),
+            ),
+        }
+    }
+
+    fn halt(&self, context: &mut B) {
+        self.resume.halt(context);
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        "UtilitySelector".into()
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        self.options.iter().map(|(_, sub)| sub.clone()).collect()
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        Ok(NodeState::new().with_child(self.index, self.resume.snapshot(context)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UtilitySelector;
+    use crate::blackboard::{Blackboard, BlackboardKey};
+    use crate::{BehaviorArc, BehaviorNode, BehaviorRunner, NodeResult};
+    use assert2::check;
+
+    const HUNGER: BlackboardKey<f32> = BlackboardKey::new("hunger");
+    const BOREDOM: BlackboardKey<f32> = BlackboardKey::new("boredom");
+    const DONE: BlackboardKey<Vec<&'static str>> = BlackboardKey::new("done");
+    const HALTED: BlackboardKey<Vec<&'static str>> = BlackboardKey::new("halted");
+
+    /// Does `name` forever, or fails right away if `fails`
+    #[derive(Debug)]
+    struct Activity {
+        name: &'static str,
+        fails: bool,
+    }
+
+    impl BehaviorNode<Blackboard> for Activity {
+        fn tick(&self, blackboard: &mut Blackboard) -> NodeResult<Blackboard> {
+            let mut done = blackboard.get_or_default(&DONE);
+            done.push(self.name);
+            blackboard.set(&DONE, done);
+            if self.fails {
+                NodeResult::Failure
+            } else {
+                NodeResult::Running(
+                    Self {
+                        name: self.name,
+                        fails: false,
+                    }
+                    .arc(),
+                )
+            }
+        }
+
+        fn halt(&self, blackboard: &mut Blackboard) {
+            let mut halted = blackboard.get_or_default(&HALTED);
+            halted.push(self.name);
+            blackboard.set(&HALTED, halted);
+        }
+    }
+
+    fn activity(name: &'static str) -> BehaviorArc<Blackboard> {
+        Activity { name, fails: false }.arc()
+    }
+
+    #[test]
+    fn test_picks_best() {
+        let mut blackboard = Blackboard::new();
+        blackboard.set(&HUNGER, 0.2);
+        blackboard.set(&BOREDOM, 0.5);
+        let tree = UtilitySelector::new()
+            .with_scored_option(HUNGER, activity("eat"))
+            .with_scored_option(BOREDOM, activity("play"))
+            .arc();
+        let mut runner = BehaviorRunner::new(tree);
+
+        check!(runner.proceed(&mut blackboard).is_none());
+        check!(blackboard.get(&DONE).unwrap() == &["play"]);
+
+        // Without hysteresis, any better score takes over
+        blackboard.set(&HUNGER, 0.6);
+        check!(runner.proceed(&mut blackboard).is_none());
+        check!(blackboard.get(&DONE).unwrap() == &["play", "eat"]);
+        check!(blackboard.get(&HALTED).unwrap() == &["play"]);
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let mut blackboard = Blackboard::new();
+        blackboard.set(&HUNGER, 0.5);
+        blackboard.set(&BOREDOM, 0.4);
+        let tree = UtilitySelector::new()
+            .with_scored_option(HUNGER, activity("eat"))
+            .with_scored_option(BOREDOM, activity("play"))
+            .with_hysteresis(0.2)
+            .arc();
+        let mut runner = BehaviorRunner::new(tree);
+        check!(runner.proceed(&mut blackboard).is_none());
+
+        // Close calls don't flip the choice...
+        blackboard.set(&BOREDOM, 0.6);
+        check!(runner.proceed(&mut blackboard).is_none());
+        check!(blackboard.get(&DONE).unwrap() == &["eat", "eat"]);
+
+        // ...but clear winners do
+        blackboard.set(&BOREDOM, 0.8);
+        check!(runner.proceed(&mut blackboard).is_none());
+        check!(blackboard.get(&DONE).unwrap() == &["eat", "eat", "play"]);
+    }
+
+    #[test]
+    fn test_falls_back() {
+        let mut blackboard = Blackboard::new();
+        let tree = UtilitySelector::new()
+            .with_option(
+                |_| 1.0,
+                Activity {
+                    name: "eat",
+                    fails: true,
+                }
+                .arc(),
+            )
+            .with_option(|_| 0.5, activity("play"))
+            .with_option(|_| 0.0, activity("sleep"))
+            .arc();
+        let mut runner = BehaviorRunner::new(tree);
+        check!(runner.proceed(&mut blackboard).is_none());
+        check!(blackboard.get(&DONE).unwrap() == &["eat", "play"]);
+
+        // Nothing worth doing
+        let tree = UtilitySelector::new()
+            .with_option(|_| 0.0, activity("sleep"))
+            .arc();
+        check!(BehaviorRunner::new(tree).proceed(&mut blackboard) == Some(false));
+    }
+}
@@ -0,0 +1,339 @@
+//! Wait for things to happen, instead of polling for them
+//!
+//! [`WaitForEvent`] and [`OnEvent`] read a Bevy event queue from their context
+//! through [`HasEvents`]. Each keeps its own place in the queue, so any number of
+//! them can listen to the same events. Only events sent after a node starts
+//! listening count, and, as with any event reader, events are missed if the node
+//! isn't ticked for longer than the queue keeps them.
+
+use crate::inspect::{short_type_name, tick_child};
+use crate::snapshot::{snapshot_decorated, NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+use bevy::ecs::event::{Event, Events};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Contexts that can read events of type `E`
+pub trait HasEvents<E: Event> {
+    /// The queue of `E`, if there is one
+    fn events(&self) -> Option<&Events<E>>;
+}
+
+impl<E: Event> HasEvents<E> for Events<E> {
+    fn events(&self) -> Option<&Events<E>> {
+        Some(self)
+    }
+}
+
+type Filter<B, E> = Arc<dyn Fn(&E, &B) -> bool + Send + Sync>;
+
+/// Where a listener got to in its queue
+enum Listened<L> {
+    /// The context has no queue for this event
+    Deaf,
+    Waiting(L),
+    Heard,
+}
+
+/// Runs until a matching event arrives, then succeeds
+///
+/// Fails if the context has no queue for `E`.
+pub struct WaitForEvent<B, E> {
+    filter: Option<Filter<B, E>>,
+    /// The id of the next event to read, set once we've started listening
+    next: Option<usize>,
+}
+
+impl<B, E> Clone for WaitForEvent<B, E> {
+    fn clone(&self) -> Self {
+        Self {
+            filter: self.filter.clone(),
+            next: self.next,
+        }
+    }
+}
+
+impl<B, E> std::fmt::Debug for WaitForEvent<B, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WaitForEvent")
+            .field("filtered", &self.filter.is_some())
+            .field("next", &self.next)
+            .finish()
+    }
+}
+
+impl<B, E> Default for WaitForEvent<B, E> {
+    fn default() -> Self {
+        Self {
+            filter: None,
+            next: None,
+        }
+    }
+}
+
+impl<B: HasEvents<E>, E: Event> WaitForEvent<B, E> {
+    /// Wait for any event of type `E`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only wait for events that `filter` accepts
+    pub fn with_filter(self, filter: impl Fn(&E, &B) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            filter: Some(Arc::new(filter)),
+            ..self
+        }
+    }
+
+    fn listening_from(&self, next: Option<usize>) -> Self {
+        Self {
+            filter: self.filter.clone(),
+            next,
+        }
+    }
+
+    /// Read the events that arrived since we last listened
+    fn listen(&self, context: &B) -> Listened<Self> {
+        let Some(events) = context.events() else {
+            return Listened::Deaf;
+        };
+        let end = events.oldest_id() + events.len();
+        let Some(next) = self.next else {
+            return Listened::Waiting(self.listening_from(Some(end)));
+        };
+        let heard = (next.max(events.oldest_id())..end)
+            .filter_map(|id| events.get_event(id))
+            .any(|(event, _)| self.accepts(event, context));
+        if heard {
+            Listened::Heard
+        } else {
+            Listened::Waiting(self.listening_from(Some(end)))
+        }
+    }
+
+    fn accepts(&self, event: &E, context: &B) -> bool {
+        match self.filter.as_ref() {
+            Some(filter) => filter(event, context),
+            None => true,
+        }
+    }
+
+    /// Start listening from the end of the queue, as event ids don't survive a save
+    fn listening_now(&self, context: &B) -> Self {
+        let end = context
+            .events()
+            .map(|events| events.oldest_id() + events.len());
+        self.listening_from(end)
+    }
+}
+
+impl<B: HasEvents<E> + 'static, E: Event> BehaviorNode<B> for WaitForEvent<B, E> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        match self.listen(context) {
+            Listened::Deaf => NodeResult::Failure,
+            Listened::Waiting(wait) => NodeResult::Running(wait.arc()),
+            Listened::Heard => NodeResult::Success,
+        }
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        format!("WaitForEvent({})", short_type_name::<E>()).into()
+    }
+
+    fn snapshot(&self, _context: &B) -> Result<NodeState, SnapshotError> {
+        Ok(NodeState::new())
+    }
+
+    fn restore(&self, _state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        Ok(self.listening_now(context).arc())
+    }
+}
+
+/// Ticks its child once a matching event arrives
+///
+/// Runs while waiting, and fails if the context has no queue for `E`.
+pub struct OnEvent<B, E> {
+    wait: WaitForEvent<B, E>,
+    child: BehaviorArc<B>,
+    /// Set once the event arrived and the child was left running
+    resume: Option<BehaviorArc<B>>,
+}
+
+impl<B, E> std::fmt::Debug for OnEvent<B, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnEvent")
+            .field("wait", &self.wait)
+            .field("child", &self.child)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B: HasEvents<E>, E: Event> OnEvent<B, E> {
+    pub fn new(child: BehaviorArc<B>) -> Self {
+        Self {
+            wait: WaitForEvent::new(),
+            child,
+            resume: None,
+        }
+    }
+
+    /// Only tick the child for events that `filter` accepts
+    pub fn with_filter(self, filter: impl Fn(&E, &B) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            wait: self.wait.with_filter(filter),
+            ..self
+        }
+    }
+
+    fn with_state(&self, wait: WaitForEvent<B, E>, resume: Option<BehaviorArc<B>>) -> Self {
+        Self {
+            wait,
+            child: self.child.clone(),
+            resume,
+        }
+    }
+}
+
+impl<B: HasEvents<E> + 'static, E: Event> BehaviorNode<B> for OnEvent<B, E> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        let result = match self.resume.as_ref() {
+            Some(resume) => tick_child(0, resume.as_ref(), context),
+            None => match self.wait.listen(context) {
+                Listened::Deaf => return NodeResult::Failure,
+                Listened::Waiting(wait) => {
+                    return NodeResult::Running(self.with_state(wait, None).arc())
+                }
+                Listened::Heard => tick_child(0, self.child.as_ref(), context),
+            },
+        };
+        match result {
+            NodeResult::Running(resume) => {
+                NodeResult::Running(self.with_state(self.wait.clone(), Some(resume)).arc())
+            }
+            result => result,
+        }
+    }
+
+    fn halt(&self, context: &mut B) {
+        if let Some(resume) = self.resume.as_ref() {
+            resume.halt(context);
+        }
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        format!("OnEvent({})", short_type_name::<E>()).into()
+    }
+
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        vec![self.child.clone()]
+    }
+
+    fn snapshot(&self, context: &B) -> Result<NodeState, SnapshotError> {
+        snapshot_decorated(self.resume.as_ref(), context)
+    }
+
+    fn restore(&self, state: &NodeState, context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        Ok(match state.child(0) {
+            Some(state) => self
+                .with_state(self.wait.clone(), Some(self.child.restore(state, context)?))
+                .arc(),
+            None => self
+                .with_state(self.wait.listening_now(context), None)
+                .arc(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HasEvents, OnEvent, WaitForEvent};
+    use crate::inspect::NodeStatus;
+    use crate::leaf::Action;
+    use crate::{BehaviorNode, BehaviorRunner};
+    use assert2::check;
+    use bevy::ecs::event::{Event, Events};
+
+    #[derive(Debug, Event)]
+    struct Hit {
+        target: u32,
+    }
+
+    #[derive(Default)]
+    struct Context {
+        me: u32,
+        hits: Events<Hit>,
+        flinches: usize,
+    }
+
+    impl HasEvents<Hit> for Context {
+        fn events(&self) -> Option<&Events<Hit>> {
+            Some(&self.hits)
+        }
+    }
+
+    #[test]
+    fn test_wait_for_event() {
+        let tree = WaitForEvent::new()
+            .with_filter(|hit: &Hit, context: &Context| hit.target == context.me)
+            .arc();
+        check!(tree.name() == "WaitForEvent(Hit)");
+        let mut context = Context {
+            me: 1,
+            ..Default::default()
+        };
+        // Events from before we started listening don't count
+        context.hits.send(Hit { target: 1 });
+        let mut runner = BehaviorRunner::new(tree);
+        check!(runner.proceed(&mut context).is_none());
+
+        context.hits.send(Hit { target: 2 });
+        check!(runner.proceed(&mut context).is_none());
+        context.hits.update();
+        check!(runner.proceed(&mut context).is_none());
+
+        context.hits.send(Hit { target: 1 });
+        context.hits.update();
+        check!(runner.proceed(&mut context) == Some(true));
+    }
+
+    #[test]
+    fn test_no_queue() {
+        let mut runner = BehaviorRunner::new(WaitForEvent::<_, Hit>::new().arc());
+        check!(runner.proceed(&mut Events::default()).is_none());
+        let mut runner = BehaviorRunner::new(WaitForEvent::<_, Hit>::new().arc());
+        check!(runner.proceed(&mut NoEvents) == Some(false));
+    }
+
+    struct NoEvents;
+
+    impl HasEvents<Hit> for NoEvents {
+        fn events(&self) -> Option<&Events<Hit>> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_on_event() {
+        let flinch = Action::from_fn(|context: &mut Context| {
+            context.flinches += 1;
+            if context.flinches < 2 {
+                NodeStatus::Running
+            } else {
+                NodeStatus::Success
+            }
+        })
+        .arc();
+        let mut runner = BehaviorRunner::new(OnEvent::<_, Hit>::new(flinch).arc());
+        let mut context = Context::default();
+        check!(runner.proceed(&mut context).is_none());
+        check!(runner.proceed(&mut context).is_none());
+        check!(context.flinches == 0);
+
+        context.hits.send(Hit { target: 0 });
+        check!(runner.proceed(&mut context).is_none());
+        check!(context.flinches == 1);
+        // The child keeps running without another event
+        check!(runner.proceed(&mut context) == Some(true));
+        check!(context.flinches == 2);
+    }
+}
@@ -0,0 +1,173 @@
+//! Draw trees as Graphviz DOT or Mermaid diagrams
+//!
+//! Nodes are labelled with their [`name`](super::BehaviorNode::name), so resumed
+//! composites are drawn as the composite they continue. Given a [`Trace`], the
+//! nodes left running by the last tick are highlighted.
+
+use crate::inspect::Trace;
+use crate::BehaviorArc;
+use std::fmt::Write;
+
+/// Draws a tree, optionally highlighting what it is running
+pub struct Diagram<'a, B> {
+    tree: &'a BehaviorArc<B>,
+    trace: Option<&'a Trace>,
+}
+
+impl<'a, B> Diagram<'a, B> {
+    pub fn new(tree: &'a BehaviorArc<B>) -> Self {
+        Self { tree, trace: None }
+    }
+
+    /// Highlight the nodes that `trace` saw running on the last tick
+    pub fn with_trace(self, trace: &'a Trace) -> Self {
+        Self {
+            trace: Some(trace),
+            ..self
+        }
+    }
+
+    /// Every node with its path, its name and if it is running, parents first
+    fn nodes(&self) -> Vec<(Vec<usize>, String, bool)> {
+        fn walk<B>(
+            node: &BehaviorArc<B>,
+            path: &mut Vec<usize>,
+            trace: Option<&Trace>,
+            nodes: &mut Vec<(Vec<usize>, String, bool)>,
+        ) {
+            let running = trace.is_some_and(|trace| trace.is_running(path));
+            nodes.push((path.clone(), node.name().into_owned(), running));
+            for (idx, child) in node.children().iter().enumerate() {
+                path.push(idx);
+                walk(child, path, trace, nodes);
+                path.pop();
+            }
+        }
+
+        let mut nodes = vec![];
+        walk(self.tree, &mut vec![], self.trace, &mut nodes);
+        nodes
+    }
+
+    /// Write the tree as a Graphviz `digraph`
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph {\n    node [shape=box];\n");
+        for (path, name, running) in self.nodes() {
+            let id = node_id(&path);
+            let label = name.replace('\\', "\\\\").replace('"', "\\\"");
+            let style = if running {
+                ", style=filled, fillcolor=gold"
+            } else {
+                ""
+            };
+            writeln!(out, "    {id} [label=\"{label}\"{style}];").unwrap();
+            if let Some((_, parent)) = path.split_last() {
+                writeln!(out, "    {} -> {id};", node_id(parent)).unwrap();
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Write the tree as a Mermaid flowchart
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("flowchart TD\n");
+        let mut running = vec![];
+        for (path, name, is_running) in self.nodes() {
+            let id = node_id(&path);
+            let label = name.replace('"', "#quot;");
+            match path.split_last() {
+                Some((_, parent)) => {
+                    writeln!(out, "    {} --> {id}[\"{label}\"]", node_id(parent)).unwrap()
+                }
+                None => writeln!(out, "    {id}[\"{label}\"]").unwrap(),
+            }
+            if is_running {
+                running.push(id);
+            }
+        }
+        if !running.is_empty() {
+            out.push_str("    classDef running fill:gold\n");
+            writeln!(out, "    class {} running", running.join(",")).unwrap();
+        }
+        out
+    }
+}
+
+/// An identifier for the node at `path` that both formats accept
+fn node_id(path: &[usize]) -> String {
+    path.iter().fold(String::from("n"), |mut id, idx| {
+        write!(id, "_{idx}").unwrap();
+        id
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Diagram;
+    use crate::composite::{Sequence, Succeeder};
+    use crate::{BehaviorNode, BehaviorRunner, NodeResult};
+    use assert2::check;
+
+    /// Runs once, then succeeds
+    #[derive(Debug, Clone)]
+    struct Twice(bool);
+
+    impl BehaviorNode<()> for Twice {
+        fn tick(&self, _: &mut ()) -> NodeResult<()> {
+            if self.0 {
+                NodeResult::Success
+            } else {
+                NodeResult::Running(Twice(true).arc())
+            }
+        }
+
+        fn name(&self) -> std::borrow::Cow<'static, str> {
+            "Say \"hi\"".into()
+        }
+    }
+
+    fn tree() -> crate::BehaviorArc<()> {
+        [Succeeder::new(Twice(true).arc()).arc(), Twice(false).arc()]
+            .into_iter()
+            .collect::<Sequence<_>>()
+            .arc()
+    }
+
+    #[test]
+    fn test_dot() {
+        let tree = tree();
+        check!(
+            Diagram::new(&tree).to_dot()
+                == "digraph {\n    node [shape=box];\n    \
+                    n [label=\"Sequence\"];\n    \
+                    n_0 [label=\"Succeeder\"];\n    \
+                    n -> n_0;\n    \
+                    n_0_0 [label=\"Say \\\"hi\\\"\"];\n    \
+                    n_0 -> n_0_0;\n    \
+                    n_1 [label=\"Say \\\"hi\\\"\"];\n    \
+                    n -> n_1;\n\
+                    }\n"
+        );
+    }
+
+    #[test]
+    fn test_mermaid_running() {
+        let mut runner = BehaviorRunner::new(tree()).with_trace(1);
+        check!(runner.proceed(&mut ()).is_none());
+        // Resumed composites are drawn as what they continue
+        let running = runner.current().unwrap().clone();
+        check!(
+            Diagram::new(&running)
+                .with_trace(runner.trace().unwrap())
+                .to_mermaid()
+                == "flowchart TD\n    \
+                    n[\"Sequence\"]\n    \
+                    n --> n_0[\"Succeeder\"]\n    \
+                    n_0 --> n_0_0[\"Say #quot;hi#quot;\"]\n    \
+                    n --> n_1[\"Say #quot;hi#quot;\"]\n    \
+                    classDef running fill:gold\n    \
+                    class n,n_1 running\n"
+        );
+    }
+}
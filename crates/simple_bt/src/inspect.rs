@@ -0,0 +1,196 @@
+//! Look inside running trees
+//!
+//! Nodes are found by their path from the root, the index of each child taken on
+//! the way down. Composites tick their children through [`tick_child`], so that a
+//! [`BehaviorRunner`](super::BehaviorRunner) with a [`Trace`] can record what
+//! every node did.
+
+use crate::profile::profile_tick;
+use crate::{BehaviorNode, NodeResult};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+/// What a node returned when it was last ticked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bevy", derive(bevy::reflect::Reflect))]
+pub enum NodeStatus {
+    Running,
+    Success,
+    Failure,
+}
+
+impl<B> From<&NodeResult<B>> for NodeStatus {
+    fn from(result: &NodeResult<B>) -> Self {
+        match result {
+            NodeResult::Running(_) => NodeStatus::Running,
+            NodeResult::Success => NodeStatus::Success,
+            NodeResult::Failure => NodeStatus::Failure,
+        }
+    }
+}
+
+type Records = Vec<(Vec<usize>, NodeStatus)>;
+
+/// Records the results of the tick in progress
+#[derive(Default)]
+struct Tracer {
+    path: Vec<usize>,
+    records: Records,
+}
+
+thread_local! {
+    static TRACER: RefCell<Option<Tracer>> = const { RefCell::new(None) };
+}
+
+/// Tick `child`, the `index`th child of the node calling this
+///
+/// Composites should tick their children through this so they show up in a [`Trace`].
+pub fn tick_child<B>(index: usize, child: &dyn BehaviorNode<B>, context: &mut B) -> NodeResult<B> {
+    let tracing = TRACER.with_borrow_mut(|tracer| {
+        tracer
+            .as_mut()
+            .map(|tracer| tracer.path.push(index))
+            .is_some()
+    });
+    let result = profile_tick(child, || child.tick(context));
+    if tracing {
+        TRACER.with_borrow_mut(|tracer| {
+            if let Some(tracer) = tracer.as_mut() {
+                tracer
+                    .records
+                    .push((tracer.path.clone(), NodeStatus::from(&result)));
+                tracer.path.pop();
+            }
+        });
+    }
+    result
+}
+
+/// Run `tick`, collecting what its nodes do if `enabled`
+///
+/// Whatever was being traced before is put aside, so trees ticked inside of
+/// other trees don't mix up their results.
+pub(crate) fn traced<R>(enabled: bool, tick: impl FnOnce() -> R) -> (R, Option<Records>) {
+    let outer = TRACER.replace(enabled.then(Tracer::default));
+    let result = tick();
+    let tracer = TRACER.replace(outer);
+    (result, tracer.map(|tracer| tracer.records))
+}
+
+/// The recent results of every node in a tree
+#[derive(Debug, Clone)]
+pub struct Trace {
+    ticks: u64,
+    capacity: usize,
+    history: HashMap<Vec<usize>, VecDeque<(u64, NodeStatus)>>,
+}
+
+impl Trace {
+    /// Keep the last `capacity` results of each node
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            ticks: 0,
+            capacity,
+            history: HashMap::new(),
+        }
+    }
+
+    /// How many ticks have been traced
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// The recent results of the node at `path`, oldest first, with the tick they happened on
+    pub fn history(&self, path: &[usize]) -> impl Iterator<Item = (u64, NodeStatus)> + '_ {
+        self.history.get(path).into_iter().flatten().copied()
+    }
+
+    pub fn last(&self, path: &[usize]) -> Option<(u64, NodeStatus)> {
+        self.history
+            .get(path)
+            .and_then(|history| history.back().copied())
+    }
+
+    /// If the node at `path` was left running by the last tick
+    pub fn is_running(&self, path: &[usize]) -> bool {
+        self.last(path) == Some((self.ticks, NodeStatus::Running))
+    }
+
+    pub(crate) fn record(&mut self, records: Records) {
+        self.ticks += 1;
+        for (path, status) in records {
+            let history = self.history.entry(path).or_default();
+            if history.len() >= self.capacity {
+                history.pop_front();
+            }
+            history.push_back((self.ticks, status));
+        }
+    }
+}
+
+/// A short name for the type `T`, without its module path or generics
+pub(crate) fn short_type_name<T: ?Sized>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    let name = name.split_once('<').map_or(name, |(name, _)| name);
+    name.rsplit_once("::").map_or(name, |(_, name)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodeStatus;
+    use crate::composite::{Inverter, Selector, Sequence};
+    use crate::test_support::After;
+    use crate::{BehaviorNode, BehaviorRunner};
+    use assert2::check;
+
+    #[test]
+    fn test_structure() {
+        let tree = [
+            Inverter::new(After::succeed(1).arc()).arc(),
+            After::succeed(2).arc(),
+        ]
+        .into_iter()
+        .collect::<Selector<()>>();
+        check!(tree.name() == "Selector");
+        let children = tree.children();
+        check!(
+            children
+                .iter()
+                .map(|child| child.name())
+                .collect::<Vec<_>>()
+                == ["Inverter", "After(2)"]
+        );
+        check!(children[0].children()[0].name() == "After(1)");
+    }
+
+    #[test]
+    fn test_trace() {
+        use NodeStatus::*;
+
+        let tree = [After::succeed(1).arc(), After::succeed(2).arc()]
+            .into_iter()
+            .collect::<Sequence<_>>()
+            .arc();
+        let mut runner = BehaviorRunner::new(tree).with_trace(2);
+
+        check!(runner.proceed(&mut ()).is_none());
+        let trace = runner.trace().unwrap();
+        check!(trace.ticks() == 1);
+        check!(trace.is_running(&[]));
+        check!(trace.last(&[0]) == Some((1, Success)));
+        check!(trace.is_running(&[1]));
+
+        // Resumed children are found where they started
+        check!(runner.proceed(&mut ()) == Some(true));
+        let trace = runner.trace().unwrap();
+        check!(!trace.is_running(&[1]));
+        check!(trace.history(&[1]).collect::<Vec<_>>() == [(1, Running), (2, Success)]);
+        // The first child wasn't ticked again
+        check!(trace.history(&[0]).collect::<Vec<_>>() == [(1, Success)]);
+
+        // Only the last few results are kept
+        check!(runner.proceed(&mut ()).is_none());
+        let trace = runner.trace().unwrap();
+        check!(trace.history(&[]).collect::<Vec<_>>() == [(2, Success), (3, Running)]);
+    }
+}
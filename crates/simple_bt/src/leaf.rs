@@ -0,0 +1,157 @@
+//! Leaves made from closures
+//!
+//! Handy for small bits of behavior that don't deserve a node type of their own.
+
+use crate::inspect::NodeStatus;
+use crate::snapshot::{mismatch, NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Does something every tick, for as long as the closure says it is running
+pub struct Action<B> {
+    name: Cow<'static, str>,
+    act: Arc<dyn Fn(&mut B) -> NodeStatus + Send + Sync>,
+}
+
+impl<B> Clone for Action<B> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            act: self.act.clone(),
+        }
+    }
+}
+
+impl<B> std::fmt::Debug for Action<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Action")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B> Action<B> {
+    /// Call `act` every tick until it returns something other than [`NodeStatus::Running`]
+    pub fn from_fn(act: impl Fn(&mut B) -> NodeStatus + Send + Sync + 'static) -> Self {
+        Self {
+            name: "Action".into(),
+            act: Arc::new(act),
+        }
+    }
+
+    /// Show up as `name` when inspected
+    pub fn with_name(self, name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: name.into(),
+            ..self
+        }
+    }
+}
+
+impl<B: 'static> BehaviorNode<B> for Action<B> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        match (self.act)(context) {
+            NodeStatus::Running => NodeResult::Running(self.clone().arc()),
+            NodeStatus::Success => NodeResult::Success,
+            NodeStatus::Failure => NodeResult::Failure,
+        }
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.name.clone()
+    }
+
+    fn snapshot(&self, _context: &B) -> Result<NodeState, SnapshotError> {
+        Ok(NodeState::new())
+    }
+
+    fn restore(&self, state: &NodeState, _context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        if *state != NodeState::new() {
+            return Err(mismatch(self));
+        }
+        Ok(self.clone().arc())
+    }
+}
+
+/// Succeeds if the closure holds, and fails otherwise
+pub struct Condition<B> {
+    name: Cow<'static, str>,
+    check: Arc<dyn Fn(&B) -> bool + Send + Sync>,
+}
+
+impl<B> std::fmt::Debug for Condition<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Condition")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B> Condition<B> {
+    pub fn from_fn(check: impl Fn(&B) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            name: "Condition".into(),
+            check: Arc::new(check),
+        }
+    }
+
+    /// Show up as `name` when inspected
+    pub fn with_name(self, name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: name.into(),
+            ..self
+        }
+    }
+}
+
+impl<B> BehaviorNode<B> for Condition<B> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        if (self.check)(context) {
+            NodeResult::Success
+        } else {
+            NodeResult::Failure
+        }
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.name.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Condition};
+    use crate::inspect::NodeStatus;
+    use crate::{BehaviorNode, BehaviorRunner};
+    use assert2::check;
+
+    #[test]
+    fn test_action() {
+        let tree = Action::from_fn(|count: &mut u32| {
+            *count += 1;
+            if *count < 3 {
+                NodeStatus::Running
+            } else {
+                NodeStatus::Success
+            }
+        })
+        .with_name("Count")
+        .arc();
+        check!(tree.name() == "Count");
+        let mut count = 0;
+        let mut runner = BehaviorRunner::new(tree);
+        check!(runner.proceed(&mut count).is_none());
+        check!(runner.proceed(&mut count).is_none());
+        check!(runner.proceed(&mut count) == Some(true));
+        check!(count == 3);
+    }
+
+    #[test]
+    fn test_condition() {
+        let tree = Condition::from_fn(|count: &u32| *count > 1).arc();
+        let mut runner = BehaviorRunner::new(tree);
+        check!(runner.proceed(&mut 1) == Some(false));
+        check!(runner.proceed(&mut 2) == Some(true));
+    }
+}
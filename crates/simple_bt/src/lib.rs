@@ -0,0 +1,197 @@
+//! Create a simple behavior tree implementation
+//!
+//! Trees run against any context type, so nothing here needs Bevy. With the `bevy`
+//! feature, trees can also think for entities through [`plugin`], be loaded as
+//! [`asset`]s, wait for [`event`]s and run async [`task`]s.
+
+#[cfg(feature = "bevy")]
+pub mod asset;
+pub mod blackboard;
+pub mod builder;
+pub mod composite;
+#[cfg(feature = "bevy")]
+pub mod event;
+pub mod export;
+pub mod inspect;
+pub mod leaf;
+#[cfg(feature = "bevy")]
+pub mod plugin;
+pub mod profile;
+pub mod random;
+pub mod registry;
+pub mod snapshot;
+#[cfg(feature = "bevy")]
+pub mod task;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod time;
+
+use inspect::{NodeStatus, Trace};
+use profile::Profile;
+use snapshot::{NodeState, SnapshotError};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub enum NodeResult<B> {
+    /// The node is still running
+    ///
+    /// This contains the node to be ticked
+    Running(BehaviorArc<B>),
+    /// The node succeeded
+    Success,
+    /// The node failed
+    Failure,
+}
+
+pub type BehaviorArc<B> = Arc<dyn BehaviorNode<B> + Send + Sync>;
+
+// This is our main "behavior tree" trait.
+// all nodes implement this trait.
+
+pub trait BehaviorNode<B>: std::fmt::Debug {
+    fn tick(&self, context: &mut B) -> NodeResult<B>;
+
+    /// Stop this node, as it won't be ticked again
+    ///
+    /// This is called on a continuation from [`NodeResult::Running`] when its
+    /// subtree is interrupted, so it can clean up. Nodes that hold running
+    /// children must halt them too.
+    fn halt(&self, _context: &mut B) {}
+
+    /// What to call this node when inspecting its tree
+    fn name(&self) -> Cow<'static, str> {
+        inspect::short_type_name::<Self>().into()
+    }
+
+    /// The children of this node, in the order they are ticked
+    fn children(&self) -> Vec<BehaviorArc<B>> {
+        vec![]
+    }
+
+    /// Save the state of this continuation from [`NodeResult::Running`]
+    ///
+    /// Nodes that can be left running implement this along with [`restore`](Self::restore),
+    /// so trees running them can be saved.
+    fn snapshot(&self, _context: &B) -> Result<NodeState, SnapshotError> {
+        Err(SnapshotError::Unsupported(self.name().into_owned()))
+    }
+
+    /// Rebuild the continuation that `state` was saved from
+    ///
+    /// This is called on the node as it is in the tree, before it was ticked.
+    fn restore(&self, _state: &NodeState, _context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        Err(SnapshotError::Unsupported(self.name().into_owned()))
+    }
+
+    fn arc(self) -> BehaviorArc<B>
+    where
+        Self: Sized + Send + Sync + 'static,
+    {
+        Arc::new(self)
+    }
+}
+
+#[derive(Debug)]
+/// Takes care of executing a behavior tree
+pub struct BehaviorRunner<B> {
+    tree: BehaviorArc<B>,
+    current_tick: Option<BehaviorArc<B>>,
+    trace: Option<Trace>,
+    profile: Option<Profile>,
+}
+
+impl<B> BehaviorRunner<B> {
+    pub fn new(tree: BehaviorArc<B>) -> Self {
+        Self {
+            tree,
+            current_tick: None,
+            trace: None,
+            profile: None,
+        }
+    }
+
+    /// Record what every node does, keeping the last `history` results of each
+    pub fn with_trace(self, history: usize) -> Self {
+        Self {
+            trace: Some(Trace::new(history)),
+            ..self
+        }
+    }
+
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    /// Measure how much work the tree does, see [`profile`]
+    pub fn with_profile(self) -> Self {
+        Self {
+            profile: Some(Profile::new()),
+            ..self
+        }
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// The tree we started from
+    pub fn tree(&self) -> &BehaviorArc<B> {
+        &self.tree
+    }
+
+    /// The continuation of the tree, if it was left running
+    pub fn current(&self) -> Option<&BehaviorArc<B>> {
+        self.current_tick.as_ref()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.current_tick.is_some()
+    }
+
+    fn tick_node(&mut self, node: &dyn BehaviorNode<B>, context: &mut B) -> Option<bool> {
+        match profile::profile_tick(node, || node.tick(context)) {
+            NodeResult::Running(nbp) => {
+                self.current_tick = Some(nbp);
+                None
+            }
+            NodeResult::Success => Some(true),
+            NodeResult::Failure => Some(false),
+        }
+    }
+
+    /// Interrupt the running tree, so the next tick starts from the top
+    pub fn halt(&mut self, context: &mut B) {
+        if let Some(bp) = self.current_tick.take() {
+            bp.halt(context);
+        }
+    }
+
+    // returns None -> still running
+    // return Some(p) -> p true success, p false failure
+    pub fn proceed(&mut self, context: &mut B) -> Option<bool> {
+        let tracing = self.trace.is_some();
+        let mut profile = self.profile.take();
+        let (result, records) = inspect::traced(tracing, || {
+            Profile::measure(profile.as_mut(), || {
+                if let Some(bp) = self.current_tick.take() {
+                    self.tick_node(bp.as_ref(), context)
+                } else {
+                    let node = self.tree.clone();
+                    self.tick_node(node.as_ref(), context)
+                }
+            })
+        });
+        self.profile = profile;
+        if let (Some(trace), Some(mut records)) = (self.trace.as_mut(), records) {
+            let status = match result {
+                None => NodeStatus::Running,
+                Some(true) => NodeStatus::Success,
+                Some(false) => NodeStatus::Failure,
+            };
+            records.push((vec![], status));
+            trace.record(records);
+        }
+        result
+    }
+}
@@ -0,0 +1,756 @@
+//! Tick behavior trees attached to entities
+
+use crate::blackboard::{Blackboard, HasBlackboard};
+use crate::event::HasEvents;
+use crate::random::HasRng;
+use crate::snapshot::{RunnerState, SnapshotError};
+use crate::time::HasTime;
+use crate::{BehaviorArc, BehaviorRunner};
+
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use std::ptr::NonNull;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+/// Ticks every [`BehaviorTree`] once per run of `S`
+pub struct BehaviorTreePlugin<S = Update> {
+    schedule: S,
+}
+
+impl Default for BehaviorTreePlugin {
+    fn default() -> Self {
+        Self { schedule: Update }
+    }
+}
+
+impl<S: ScheduleLabel + Clone> BehaviorTreePlugin<S> {
+    /// Tick trees in `schedule` instead of [`Update`]
+    pub fn in_schedule(schedule: S) -> Self {
+        Self { schedule }
+    }
+}
+
+impl<S: ScheduleLabel + Clone> Plugin for BehaviorTreePlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BehaviorFinished>()
+            .register_type::<BehaviorTree>()
+            .register_type::<BehaviorFinished>()
+            .init_resource::<BehaviorRng>()
+            .init_resource::<DroppedTrees>()
+            .add_systems(
+                self.schedule.clone(),
+                (halt_dropped_trees, tick_behavior_trees)
+                    .chain()
+                    .in_set(BehaviorTreeSet),
+            );
+    }
+}
+
+/// System set that behavior trees are ticked in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub struct BehaviorTreeSet;
+
+/// Sent whenever an entity's tree completes
+///
+/// The tree starts over the next time it is ticked.
+#[derive(Debug, Clone, Copy, Event, Reflect)]
+pub struct BehaviorFinished {
+    pub entity: Entity,
+    pub success: bool,
+}
+
+/// The dice behavior trees roll
+///
+/// Insert a [seeded](BehaviorRng::seeded) one to make trees choose the same way every run.
+#[derive(Resource)]
+pub struct BehaviorRng(pub StdRng);
+
+impl BehaviorRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl Default for BehaviorRng {
+    fn default() -> Self {
+        Self(StdRng::from_entropy())
+    }
+}
+
+/// A behavior tree that thinks for the entity it is attached to
+///
+/// Only whether it is active and how it is inspected are reflected.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct BehaviorTree {
+    /// Only active trees are ticked
+    pub active: bool,
+    // Taken while the tree is being ticked
+    #[reflect(ignore)]
+    runner: Option<BehaviorRunner<EntityContext>>,
+    #[reflect(ignore)]
+    blackboard: Blackboard,
+    /// How many results to trace per node, if tracing
+    trace: Option<usize>,
+    profile: bool,
+    /// Where to send the runner to be halted if we're dropped while it runs, once ticked
+    #[reflect(ignore)]
+    dropped: Option<(Entity, Sender<DroppedTree>)>,
+}
+
+/// A tree that doesn't think until it is given a tree with [`BehaviorTree::set_tree`]
+impl Default for BehaviorTree {
+    fn default() -> Self {
+        Self {
+            active: true,
+            runner: None,
+            blackboard: Blackboard::new(),
+            trace: None,
+            profile: false,
+            dropped: None,
+        }
+    }
+}
+
+/// Running trees are halted when their component is removed or their entity despawned
+impl Drop for BehaviorTree {
+    fn drop(&mut self) {
+        let Some((entity, dropped)) = self.dropped.take() else {
+            return;
+        };
+        if let Some(runner) = self.runner.take().filter(BehaviorRunner::is_running) {
+            // Nobody is left to halt it if the app is gone too
+            let _ = dropped.send(DroppedTree {
+                entity,
+                runner,
+                blackboard: std::mem::take(&mut self.blackboard),
+            });
+        }
+    }
+}
+
+impl BehaviorTree {
+    pub fn new(tree: BehaviorArc<EntityContext>) -> Self {
+        let mut behavior = Self::default();
+        behavior.runner = Some(BehaviorRunner::new(tree));
+        behavior
+    }
+
+    pub fn with_active(mut self, active: bool) -> Self {
+        self.active = active;
+        self
+    }
+
+    pub fn with_blackboard(mut self, blackboard: Blackboard) -> Self {
+        self.blackboard = blackboard;
+        self
+    }
+
+    /// Trace the tree for inspection, see [`BehaviorRunner::with_trace`]
+    pub fn with_trace(mut self, history: usize) -> Self {
+        self.runner = self.runner.take().map(|runner| runner.with_trace(history));
+        self.trace = Some(history);
+        self
+    }
+
+    /// Profile the tree, see [`BehaviorRunner::with_profile`]
+    pub fn with_profile(mut self) -> Self {
+        self.runner = self.runner.take().map(BehaviorRunner::with_profile);
+        self.profile = true;
+        self
+    }
+
+    /// Think with `tree` from now on, starting from the top
+    ///
+    /// The old tree isn't halted, so only use this on trees that aren't running.
+    /// [`replace_tree`](Self::replace_tree) halts the old tree first.
+    pub fn set_tree(&mut self, tree: BehaviorArc<EntityContext>) {
+        self.runner = Some(self.instrument(BehaviorRunner::new(tree)));
+    }
+
+    /// Halt what `entity` is thinking, and think with `tree` from now on
+    ///
+    /// Returns whether the entity has a tree to replace.
+    pub fn replace_tree(
+        world: &mut World,
+        entity: Entity,
+        tree: BehaviorArc<EntityContext>,
+    ) -> bool {
+        lend_runner(world, entity, |runner, context| {
+            if let Some(old) = runner.as_mut() {
+                old.halt(context);
+            }
+            let new = BehaviorRunner::new(tree);
+            *runner = Some(match context.world().get::<BehaviorTree>(entity) {
+                Some(tree) => tree.instrument(new),
+                None => new,
+            });
+        })
+        .is_some()
+    }
+
+    /// Trace and profile `runner` as this tree is traced and profiled
+    fn instrument(&self, runner: BehaviorRunner<EntityContext>) -> BehaviorRunner<EntityContext> {
+        let runner = match self.trace {
+            Some(history) => runner.with_trace(history),
+            None => runner,
+        };
+        if self.profile {
+            runner.with_profile()
+        } else {
+            runner
+        }
+    }
+
+    pub fn runner(&self) -> Option<&BehaviorRunner<EntityContext>> {
+        self.runner.as_ref()
+    }
+
+    pub fn has_tree(&self) -> bool {
+        self.runner.is_some()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.runner.as_ref().is_some_and(BehaviorRunner::is_running)
+    }
+
+    pub fn blackboard(&self) -> &Blackboard {
+        &self.blackboard
+    }
+
+    pub fn blackboard_mut(&mut self) -> &mut Blackboard {
+        &mut self.blackboard
+    }
+
+    /// Save where the tree of `entity` is, see [`BehaviorRunner::snapshot`]
+    ///
+    /// Entities without a tree save as not running.
+    pub fn snapshot(world: &mut World, entity: Entity) -> Result<RunnerState, SnapshotError> {
+        lend_runner(world, entity, |runner, context| match runner {
+            Some(runner) => runner.snapshot(context),
+            None => Ok(RunnerState::default()),
+        })
+        .unwrap_or_else(|| Ok(RunnerState::default()))
+    }
+
+    /// Pick the tree of `entity` up from where `state` was saved
+    ///
+    /// The entity must already have the tree the state was saved from. Returns
+    /// whether it had a tree to restore.
+    pub fn restore(
+        world: &mut World,
+        entity: Entity,
+        state: &RunnerState,
+    ) -> Result<bool, SnapshotError> {
+        lend_runner(world, entity, |runner, context| {
+            let Some(current) = runner.as_ref() else {
+                return Ok(false);
+            };
+            let restored = BehaviorRunner::restore(current.tree().clone(), state, context)?;
+            *runner = Some(match context.world().get::<BehaviorTree>(entity) {
+                Some(tree) => tree.instrument(restored),
+                None => restored,
+            });
+            Ok(true)
+        })
+        .unwrap_or(Ok(false))
+    }
+}
+
+/// What nodes of a [`BehaviorTree`] get to see
+///
+/// This gives access to the components of the entity the tree belongs to,
+/// and to the world's resources and events.
+#[derive(TypePath)]
+pub struct EntityContext {
+    /// The world, borrowed for as long as the context is lent out, see [`EntityContext::lend`]
+    ///
+    /// Trees are stored in components and assets, so the context they run with can't
+    /// carry the lifetime of the borrow.
+    world: NonNull<World>,
+    entity: Entity,
+    blackboard: Blackboard,
+}
+
+// SAFETY: the context stands in for a `&mut World`, which is `Send` and `Sync`
+unsafe impl Send for EntityContext {}
+unsafe impl Sync for EntityContext {}
+
+impl EntityContext {
+    /// Lend `world` to a context for `entity` while `f` runs
+    ///
+    /// Contexts are only made here and only handed out by `&mut`, so none can be
+    /// kept past `f`, and the world is only reached through the context until then.
+    fn lend<R>(world: &mut World, entity: Entity, f: impl FnOnce(&mut Self) -> R) -> R {
+        let mut context = Self {
+            world: NonNull::from(world),
+            entity,
+            blackboard: Blackboard::new(),
+        };
+        f(&mut context)
+    }
+
+    fn world(&self) -> &World {
+        // SAFETY: the world is borrowed for as long as we're lent out, see `lend`
+        unsafe { self.world.as_ref() }
+    }
+
+    fn world_mut(&mut self) -> &mut World {
+        // SAFETY: as in `world`, and `&mut self` keeps this the only borrow
+        unsafe { self.world.as_mut() }
+    }
+
+    /// The entity that is thinking
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    pub fn get<C: Component>(&self) -> Option<&C> {
+        self.world().get(self.entity)
+    }
+
+    pub fn get_mut<C: Component>(&mut self) -> Option<Mut<'_, C>> {
+        let entity = self.entity;
+        self.world_mut().get_mut(entity)
+    }
+
+    pub fn contains<C: Component>(&self) -> bool {
+        self.get::<C>().is_some()
+    }
+
+    pub fn insert(&mut self, bundle: impl Bundle) {
+        let entity = self.entity;
+        if let Some(mut entity) = self.world_mut().get_entity_mut(entity) {
+            entity.insert(bundle);
+        }
+    }
+
+    pub fn remove<T: Bundle>(&mut self) {
+        let entity = self.entity;
+        if let Some(mut entity) = self.world_mut().get_entity_mut(entity) {
+            entity.remove::<T>();
+        }
+    }
+
+    pub fn resource<R: Resource>(&self) -> Option<&R> {
+        self.world().get_resource()
+    }
+
+    pub fn resource_mut<R: Resource>(&mut self) -> Option<Mut<'_, R>> {
+        self.world_mut().get_resource_mut()
+    }
+
+    pub fn send_event<E: Event>(&mut self, event: E) {
+        self.world_mut().send_event(event);
+    }
+}
+
+impl HasBlackboard for EntityContext {
+    fn blackboard(&self) -> &Blackboard {
+        &self.blackboard
+    }
+
+    fn blackboard_mut(&mut self) -> &mut Blackboard {
+        &mut self.blackboard
+    }
+}
+
+/// Reads the world's queues, for events added with [`App::add_event`]
+impl<E: Event> HasEvents<E> for EntityContext {
+    fn events(&self) -> Option<&Events<E>> {
+        self.resource()
+    }
+}
+
+/// Reads the [`Time`] of the schedule trees are ticked in
+impl HasTime for EntityContext {
+    fn elapsed(&self) -> Duration {
+        self.resource::<Time>()
+            .map_or(Duration::ZERO, Time::elapsed)
+    }
+}
+
+/// Rolls the world's [`BehaviorRng`]
+impl HasRng for EntityContext {
+    fn rng(&mut self) -> &mut dyn RngCore {
+        &mut self
+            .world_mut()
+            .get_resource_or_insert_with(BehaviorRng::default)
+            .into_inner()
+            .0
+    }
+}
+
+/// Lend `world` to a context for `entity`, along with the runner of its tree
+///
+/// Returns `None` if the entity has no [`BehaviorTree`].
+fn lend_runner<R>(
+    world: &mut World,
+    entity: Entity,
+    f: impl FnOnce(&mut Option<BehaviorRunner<EntityContext>>, &mut EntityContext) -> R,
+) -> Option<R> {
+    let mut tree = world.get_mut::<BehaviorTree>(entity)?;
+    let mut runner = tree.runner.take();
+    let mut blackboard = std::mem::take(&mut tree.blackboard);
+    let result = EntityContext::lend(world, entity, |context| {
+        std::mem::swap(&mut context.blackboard, &mut blackboard);
+        let result = f(&mut runner, context);
+        std::mem::swap(&mut context.blackboard, &mut blackboard);
+        result
+    });
+    if let Some(runner) = runner {
+        put_back(world, entity, runner, blackboard);
+    }
+    Some(result)
+}
+
+/// A runner whose [`BehaviorTree`] was dropped while it was running
+struct DroppedTree {
+    entity: Entity,
+    runner: BehaviorRunner<EntityContext>,
+    blackboard: Blackboard,
+}
+
+/// Trees dropped while running, waiting to be halted
+#[derive(Resource)]
+struct DroppedTrees {
+    sender: Sender<DroppedTree>,
+    receiver: Mutex<Receiver<DroppedTree>>,
+}
+
+impl Default for DroppedTrees {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver: Mutex::new(receiver),
+        }
+    }
+}
+
+/// Hand `runner` back to the tree of `entity`, or halt it if the tree is gone
+fn put_back(
+    world: &mut World,
+    entity: Entity,
+    runner: BehaviorRunner<EntityContext>,
+    blackboard: Blackboard,
+) {
+    let sender = world
+        .get_resource::<DroppedTrees>()
+        .map(|dropped| dropped.sender.clone());
+    match world.get_mut::<BehaviorTree>(entity) {
+        Some(mut tree) => {
+            tree.runner = Some(runner);
+            tree.blackboard = blackboard;
+            if tree.dropped.is_none() {
+                tree.dropped = sender.map(|sender| (entity, sender));
+            }
+        }
+        // The tree was removed while thinking
+        None => halt_dropped(
+            world,
+            DroppedTree {
+                entity,
+                runner,
+                blackboard,
+            },
+        ),
+    }
+}
+
+fn halt_dropped(world: &mut World, mut dropped: DroppedTree) {
+    EntityContext::lend(world, dropped.entity, |context| {
+        context.blackboard = dropped.blackboard;
+        dropped.runner.halt(context);
+    });
+}
+
+fn halt_dropped_trees(world: &mut World) {
+    let dropped = match world.get_resource::<DroppedTrees>() {
+        Some(dropped) => dropped
+            .receiver
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .try_iter()
+            .collect::<Vec<_>>(),
+        None => return,
+    };
+    for dropped in dropped {
+        halt_dropped(world, dropped);
+    }
+}
+
+fn tick_behavior_trees(world: &mut World, trees: &mut QueryState<(Entity, &mut BehaviorTree)>) {
+    let mut thinking = vec![];
+    for (entity, mut tree) in trees.iter_mut(world) {
+        if !tree.active {
+            continue;
+        }
+        if let Some(runner) = tree.runner.take() {
+            let blackboard = std::mem::take(&mut tree.blackboard);
+            thinking.push((entity, runner, blackboard));
+        }
+    }
+    if thinking.is_empty() {
+        return;
+    }
+
+    let mut finished = vec![];
+    EntityContext::lend(world, Entity::PLACEHOLDER, |context| {
+        for (entity, runner, blackboard) in thinking.iter_mut() {
+            context.entity = *entity;
+            std::mem::swap(&mut context.blackboard, blackboard);
+            if let Some(success) = runner.proceed(context) {
+                finished.push(BehaviorFinished {
+                    entity: *entity,
+                    success,
+                });
+            }
+            std::mem::swap(&mut context.blackboard, blackboard);
+        }
+    });
+
+    for (entity, runner, blackboard) in thinking {
+        put_back(world, entity, runner, blackboard);
+    }
+    world.send_event_batch(finished);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        tick_behavior_trees, BehaviorFinished, BehaviorTree, BehaviorTreePlugin, EntityContext,
+    };
+    use crate::composite::Sequence;
+    use crate::event::OnEvent;
+    use crate::snapshot::{NodeState, SnapshotError};
+    use crate::{BehaviorArc, BehaviorNode, NodeResult};
+    use assert2::check;
+    use bevy::prelude::*;
+
+    #[derive(Component)]
+    struct Steps(usize);
+
+    #[derive(Resource)]
+    struct Goal(usize);
+
+    /// Takes steps until reaching the [`Goal`]
+    #[derive(Debug)]
+    struct Walk;
+
+    impl BehaviorNode<EntityContext> for Walk {
+        fn tick(&self, context: &mut EntityContext) -> NodeResult<EntityContext> {
+            let Some(goal) = context.resource::<Goal>().map(|goal| goal.0) else {
+                return NodeResult::Failure;
+            };
+            let Some(mut steps) = context.get_mut::<Steps>() else {
+                return NodeResult::Failure;
+            };
+            steps.0 += 1;
+            if steps.0 >= goal {
+                NodeResult::Success
+            } else {
+                NodeResult::Running(Walk.arc())
+            }
+        }
+
+        fn snapshot(&self, _: &EntityContext) -> Result<NodeState, SnapshotError> {
+            Ok(NodeState::new())
+        }
+
+        fn restore(
+            &self,
+            _: &NodeState,
+            _: &EntityContext,
+        ) -> Result<BehaviorArc<EntityContext>, SnapshotError> {
+            Ok(Walk.arc())
+        }
+    }
+
+    #[derive(Component)]
+    struct Songs(usize);
+
+    /// Sings a song before it goes
+    #[derive(Debug)]
+    struct Sing;
+
+    impl BehaviorNode<EntityContext> for Sing {
+        fn tick(&self, context: &mut EntityContext) -> NodeResult<EntityContext> {
+            match context.get_mut::<Songs>() {
+                Some(mut songs) => {
+                    songs.0 += 1;
+                    NodeResult::Success
+                }
+                None => NodeResult::Failure,
+            }
+        }
+    }
+
+    #[test]
+    fn test_plugin_ticks_trees() {
+        let mut app = App::new();
+        app.add_plugins(BehaviorTreePlugin::default())
+            .insert_resource(Goal(2));
+        let walker = app
+            .world
+            .spawn((Steps(0), BehaviorTree::new(Walk.arc())))
+            .id();
+        let lazy = app
+            .world
+            .spawn((Steps(0), BehaviorTree::new(Walk.arc()).with_active(false)))
+            .id();
+        let lost = app.world.spawn(BehaviorTree::new(Walk.arc())).id();
+
+        app.update();
+        check!(app.world.get::<Steps>(walker).unwrap().0 == 1);
+        check!(app.world.get::<BehaviorTree>(walker).unwrap().is_running());
+        app.update();
+        check!(app.world.get::<Steps>(walker).unwrap().0 == 2);
+        check!(app.world.get::<Steps>(lazy).unwrap().0 == 0);
+
+        let finished = app
+            .world
+            .resource::<Events<BehaviorFinished>>()
+            .iter_current_update_events()
+            .map(|event| (event.entity, event.success))
+            .collect::<Vec<_>>();
+        check!(finished.len() == 2);
+        check!(finished.contains(&(walker, true)));
+        // Failing trees are reported too
+        check!(finished.contains(&(lost, false)));
+    }
+
+    #[derive(Event)]
+    struct Encore(Entity);
+
+    #[test]
+    fn test_events() {
+        let mut app = App::new();
+        app.add_plugins(BehaviorTreePlugin::default())
+            .add_event::<Encore>();
+        let tree = OnEvent::new(Sing.arc())
+            .with_filter(|encore: &Encore, context: &EntityContext| encore.0 == context.entity())
+            .arc();
+        let singer = app
+            .world
+            .spawn((Songs(0), BehaviorTree::new(tree.clone())))
+            .id();
+        let other = app.world.spawn((Songs(0), BehaviorTree::new(tree))).id();
+
+        app.update();
+        app.update();
+        check!(app.world.get::<Songs>(singer).unwrap().0 == 0);
+        app.world.send_event(Encore(singer));
+        app.update();
+        check!(app.world.get::<Songs>(singer).unwrap().0 == 1);
+        check!(app.world.get::<Songs>(other).unwrap().0 == 0);
+    }
+
+    #[test]
+    fn test_save_and_restore() {
+        let mut app = App::new();
+        app.add_plugins(BehaviorTreePlugin::default())
+            .insert_resource(Goal(3));
+        let tree = [Sing.arc(), Walk.arc()]
+            .into_iter()
+            .collect::<Sequence<_>>()
+            .arc();
+        let walker = app
+            .world
+            .spawn((Steps(0), Songs(0), BehaviorTree::new(tree.clone())))
+            .id();
+        app.update();
+        let state = BehaviorTree::snapshot(&mut app.world, walker).unwrap();
+        check!(state.running.is_some());
+
+        // As if loaded from a save
+        let loaded = app
+            .world
+            .spawn((Steps(1), Songs(1), BehaviorTree::new(tree)))
+            .id();
+        check!(BehaviorTree::restore(&mut app.world, loaded, &state).unwrap());
+        check!(app.world.get::<BehaviorTree>(loaded).unwrap().is_running());
+        app.update();
+        app.update();
+        // Picks up walking, without singing again
+        check!(app.world.get::<Steps>(loaded).unwrap().0 == 3);
+        check!(app.world.get::<Songs>(loaded).unwrap().0 == 1);
+    }
+
+    #[derive(Resource, Default)]
+    struct Halts(usize);
+
+    /// Runs until halted, counting [`Halts`]
+    #[derive(Debug)]
+    struct Daydream;
+
+    impl BehaviorNode<EntityContext> for Daydream {
+        fn tick(&self, _: &mut EntityContext) -> NodeResult<EntityContext> {
+            NodeResult::Running(Daydream.arc())
+        }
+
+        fn halt(&self, context: &mut EntityContext) {
+            if let Some(mut halts) = context.resource_mut::<Halts>() {
+                halts.0 += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_dropped_trees_halt() {
+        let mut app = App::new();
+        app.add_plugins(BehaviorTreePlugin::default())
+            .init_resource::<Halts>();
+        let despawned = app.world.spawn(BehaviorTree::new(Daydream.arc())).id();
+        let removed = app.world.spawn(BehaviorTree::new(Daydream.arc())).id();
+        let replaced = app.world.spawn(BehaviorTree::new(Daydream.arc())).id();
+        app.update();
+        check!(app.world.resource::<Halts>().0 == 0);
+
+        app.world.despawn(despawned);
+        app.world.entity_mut(removed).remove::<BehaviorTree>();
+        app.update();
+        check!(app.world.resource::<Halts>().0 == 2);
+
+        check!(BehaviorTree::replace_tree(
+            &mut app.world,
+            replaced,
+            Walk.arc()
+        ));
+        check!(app.world.resource::<Halts>().0 == 3);
+        check!(!app
+            .world
+            .get::<BehaviorTree>(replaced)
+            .unwrap()
+            .is_running());
+    }
+
+    /// Panics when ticked
+    #[derive(Debug)]
+    struct Trip;
+
+    impl BehaviorNode<EntityContext> for Trip {
+        fn tick(&self, _: &mut EntityContext) -> NodeResult<EntityContext> {
+            panic!("tripped");
+        }
+    }
+
+    #[test]
+    fn test_panics_keep_the_world() {
+        let mut world = World::new();
+        world.init_resource::<Events<BehaviorFinished>>();
+        world.insert_resource(Goal(2));
+        let walker = world.spawn((Steps(0), BehaviorTree::new(Trip.arc()))).id();
+        let mut trees = world.query();
+        let ticked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            tick_behavior_trees(&mut world, &mut trees)
+        }));
+        check!(ticked.is_err());
+        check!(world.resource::<Goal>().0 == 2);
+        check!(world.get::<Steps>(walker).is_some());
+    }
+}
@@ -0,0 +1,303 @@
+//! Measure how much work trees do
+//!
+//! A [`BehaviorRunner`](super::BehaviorRunner) made [`with_profile`](super::BehaviorRunner::with_profile)
+//! times every node it ticks, and sums up the results by node type. Nodes are
+//! timed as they are ticked through [`tick_child`](super::inspect::tick_child),
+//! so composites need nothing extra for their children to be measured.
+
+use crate::inspect::NodeStatus;
+use crate::{BehaviorNode, NodeResult};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// One tick of one node
+struct Sample {
+    name: Cow<'static, str>,
+    status: NodeStatus,
+    total: Duration,
+    /// Time not spent in children
+    own: Duration,
+}
+
+/// Collects samples for the tick in progress
+#[derive(Default)]
+struct Profiler {
+    /// Time spent in children, for each node being ticked
+    children: Vec<Duration>,
+    samples: Vec<Sample>,
+}
+
+thread_local! {
+    static PROFILER: RefCell<Option<Profiler>> = const { RefCell::new(None) };
+}
+
+/// Run `tick`, the tick of `node`, timing it if we are profiling
+pub(crate) fn profile_tick<B>(
+    node: &dyn BehaviorNode<B>,
+    tick: impl FnOnce() -> NodeResult<B>,
+) -> NodeResult<B> {
+    let profiling = PROFILER.with_borrow_mut(|profiler| {
+        profiler
+            .as_mut()
+            .map(|profiler| profiler.children.push(Duration::ZERO))
+            .is_some()
+    });
+    if !profiling {
+        return tick();
+    }
+    let start = Instant::now();
+    let result = tick();
+    let total = start.elapsed();
+    PROFILER.with_borrow_mut(|profiler| {
+        if let Some(profiler) = profiler.as_mut() {
+            let children = profiler.children.pop().unwrap_or_default();
+            if let Some(parent) = profiler.children.last_mut() {
+                *parent += total;
+            }
+            profiler.samples.push(Sample {
+                name: node.name(),
+                status: NodeStatus::from(&result),
+                total,
+                own: total.saturating_sub(children),
+            });
+        }
+    });
+    result
+}
+
+/// Run `tick`, timing its nodes
+///
+/// Like [`traced`](super::inspect::traced), whatever was being profiled before is put aside.
+fn profiled<R>(tick: impl FnOnce() -> R) -> (R, Vec<Sample>) {
+    let outer = PROFILER.replace(Some(Profiler::default()));
+    let result = tick();
+    let profiler = PROFILER.replace(outer);
+    (
+        result,
+        profiler
+            .map(|profiler| profiler.samples)
+            .unwrap_or_default(),
+    )
+}
+
+/// What the nodes of one type did
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NodeStats {
+    pub ticks: u64,
+    pub running: u64,
+    pub successes: u64,
+    pub failures: u64,
+    /// Time spent ticking, children included
+    pub total_time: Duration,
+    /// Time spent ticking, not counting children
+    pub own_time: Duration,
+}
+
+impl NodeStats {
+    /// The share of completed ticks that succeeded
+    pub fn success_ratio(&self) -> Option<f64> {
+        let completed = self.successes + self.failures;
+        (completed > 0).then(|| self.successes as f64 / completed as f64)
+    }
+
+    fn merge(&mut self, other: &NodeStats) {
+        self.ticks += other.ticks;
+        self.running += other.running;
+        self.successes += other.successes;
+        self.failures += other.failures;
+        self.total_time += other.total_time;
+        self.own_time += other.own_time;
+    }
+}
+
+/// How much work a runner has done
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    proceeds: u64,
+    time: Duration,
+    nodes_ticked: u64,
+    most_nodes_ticked: usize,
+    running_streak: u64,
+    longest_running_streak: u64,
+    nodes: BTreeMap<String, NodeStats>,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How often the runner proceeded
+    pub fn proceeds(&self) -> u64 {
+        self.proceeds
+    }
+
+    /// Time spent proceeding
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// The average number of nodes ticked per proceed
+    pub fn mean_nodes_ticked(&self) -> f64 {
+        self.nodes_ticked as f64 / self.proceeds.max(1) as f64
+    }
+
+    /// The most nodes ticked in one proceed
+    pub fn most_nodes_ticked(&self) -> usize {
+        self.most_nodes_ticked
+    }
+
+    /// The most proceeds in a row that left the tree running
+    pub fn longest_running_streak(&self) -> u64 {
+        self.longest_running_streak
+    }
+
+    /// Stats by node type, which is the node's name without its parameters
+    pub fn nodes(&self) -> impl Iterator<Item = (&str, &NodeStats)> {
+        self.nodes
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats))
+    }
+
+    pub fn node(&self, name: &str) -> Option<&NodeStats> {
+        self.nodes.get(name)
+    }
+
+    /// Add up the work of another runner, to profile many trees at once
+    pub fn merge(&mut self, other: &Profile) {
+        self.proceeds += other.proceeds;
+        self.time += other.time;
+        self.nodes_ticked += other.nodes_ticked;
+        self.most_nodes_ticked = self.most_nodes_ticked.max(other.most_nodes_ticked);
+        self.longest_running_streak = self
+            .longest_running_streak
+            .max(other.longest_running_streak);
+        for (name, stats) in &other.nodes {
+            self.nodes.entry(name.clone()).or_default().merge(stats);
+        }
+    }
+
+    /// Stats by node type, as CSV with a header
+    pub fn to_csv(&self) -> String {
+        let mut csv =
+            String::from("node,ticks,running,successes,failures,total_us,own_us,mean_own_us\n");
+        for (name, stats) in &self.nodes {
+            writeln!(
+                csv,
+                "\"{}\",{},{},{},{},{},{},{:.3}",
+                name.replace('"', "\"\""),
+                stats.ticks,
+                stats.running,
+                stats.successes,
+                stats.failures,
+                stats.total_time.as_micros(),
+                stats.own_time.as_micros(),
+                stats.own_time.as_secs_f64() * 1e6 / stats.ticks.max(1) as f64,
+            )
+            .unwrap();
+        }
+        csv
+    }
+
+    fn record(&mut self, time: Duration, running: bool, samples: Vec<Sample>) {
+        self.proceeds += 1;
+        self.time += time;
+        self.nodes_ticked += samples.len() as u64;
+        self.most_nodes_ticked = self.most_nodes_ticked.max(samples.len());
+        self.running_streak = if running { self.running_streak + 1 } else { 0 };
+        self.longest_running_streak = self.longest_running_streak.max(self.running_streak);
+        for sample in samples {
+            let kind = sample
+                .name
+                .split_once('(')
+                .map_or(&*sample.name, |(kind, _)| kind);
+            let stats = self.nodes.entry(kind.to_owned()).or_default();
+            stats.ticks += 1;
+            match sample.status {
+                NodeStatus::Running => stats.running += 1,
+                NodeStatus::Success => stats.successes += 1,
+                NodeStatus::Failure => stats.failures += 1,
+            }
+            stats.total_time += sample.total;
+            stats.own_time += sample.own;
+        }
+    }
+
+    /// Run `proceed`, recording its work if `profile` is set
+    pub(crate) fn measure(
+        profile: Option<&mut Profile>,
+        proceed: impl FnOnce() -> Option<bool>,
+    ) -> Option<bool> {
+        let Some(profile) = profile else {
+            return proceed();
+        };
+        let start = Instant::now();
+        let (result, samples) = profiled(proceed);
+        profile.record(start.elapsed(), result.is_none(), samples);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::composite::{Inverter, Sequence};
+    use crate::test_support::After;
+    use crate::{BehaviorNode, BehaviorRunner};
+    use assert2::check;
+
+    #[test]
+    fn test_profile() {
+        let tree = [
+            Inverter::new(After::succeed(1).arc()).arc(),
+            After::succeed(3).arc(),
+        ]
+        .into_iter()
+        .collect::<Sequence<_>>()
+        .arc();
+        let mut runner = BehaviorRunner::new(tree).with_profile();
+        check!(runner.proceed(&mut ()) == Some(false));
+        for _ in 0..3 {
+            runner.proceed(&mut ());
+        }
+        check!(runner.proceed(&mut ()) == Some(false));
+
+        let profile = runner.profile().unwrap();
+        check!(profile.proceeds() == 5);
+        check!(profile.most_nodes_ticked() == 3);
+        check!(profile.longest_running_streak() == 0);
+        let sequence = profile.node("Sequence").unwrap();
+        check!(sequence.ticks == 5);
+        check!(sequence.failures == 5);
+        check!(sequence.success_ratio() == Some(0.0));
+        check!(sequence.own_time <= sequence.total_time);
+        // Parameters are left out of the node type
+        check!(profile.node("After").unwrap().ticks == 5);
+
+        let csv = profile.to_csv();
+        let mut lines = csv.lines();
+        check!(lines.next().unwrap().starts_with("node,ticks"));
+        check!(lines.next().unwrap().starts_with("\"After\",5,0,5,0,"));
+        check!(lines.count() == 2);
+    }
+
+    #[test]
+    fn test_running_streak() {
+        let tree = [After::succeed(1).arc(), After::succeed(3).arc()]
+            .into_iter()
+            .collect::<Sequence<_>>()
+            .arc();
+        let mut runner = BehaviorRunner::new(tree).with_profile();
+        while runner.proceed(&mut ()).is_none() {}
+        runner.proceed(&mut ());
+
+        let profile = runner.profile().unwrap();
+        check!(profile.proceeds() == 4);
+        check!(profile.longest_running_streak() == 2);
+        check!(profile.mean_nodes_ticked() == 2.5);
+        let after = profile.node("After").unwrap();
+        check!((after.running, after.successes) == (3, 3));
+    }
+}
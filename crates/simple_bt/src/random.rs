@@ -0,0 +1,18 @@
+//! Let nodes roll dice
+//!
+//! Nodes that make random choices, like [`RandomSelector`](super::composite::RandomSelector),
+//! draw from the context's RNG through [`HasRng`], so seeding it replays the same choices.
+
+use rand::rngs::StdRng;
+use rand::RngCore;
+
+/// Contexts that have a random number generator
+pub trait HasRng {
+    fn rng(&mut self) -> &mut dyn RngCore;
+}
+
+impl HasRng for StdRng {
+    fn rng(&mut self) -> &mut dyn RngCore {
+        self
+    }
+}
@@ -0,0 +1,661 @@
+//! Build behavior trees from RON
+//!
+//! Every node is written as its registered name, followed by its parameters in parentheses:
+//!
+//! ```ron
+//! Repeated(Sequence([
+//!     MoveTo(speed: 250.0, goal: (300.0, 0.0)),
+//!     LimitedRepeated(limit: 2, child: Bark()),
+//! ]))
+//! ```
+//!
+//! Nodes without parameters still need their (empty) parentheses.
+
+use crate::blackboard::{BlackboardKey, HasBlackboard};
+use crate::composite::{
+    Cooldown, Delay, Inverter, LimitedRepeated, Parallel, ParallelPolicy, RandomSelector,
+    ReactiveSelector, ReactiveSequence, Repeated, RepeatedUntilFailure, Scoped, Selector, Sequence,
+    ShuffledSequence, Succeeder, Timeout, UtilitySelector, Wait, WeightedChoice,
+};
+use crate::random::HasRng;
+use crate::time::HasTime;
+use crate::{BehaviorArc, BehaviorNode};
+
+use ron::error::SpannedError;
+use ron::extensions::Extensions;
+use serde::de::{
+    DeserializeOwned, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::time::Duration;
+
+type Builder<B> = Box<
+    dyn for<'de> Fn(
+            &mut dyn erased_serde::Deserializer<'de>,
+            &BehaviorRegistry<B>,
+        ) -> Result<BehaviorArc<B>, erased_serde::Error>
+        + Send
+        + Sync,
+>;
+
+/// Knows how to build each kind of node by name
+///
+/// The built-in composites are registered on creation.
+pub struct BehaviorRegistry<B> {
+    builders: HashMap<String, Builder<B>>,
+}
+
+impl<B> std::fmt::Debug for BehaviorRegistry<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BehaviorRegistry")
+            .field("nodes", &self.builders.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<B: 'static> Default for BehaviorRegistry<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: 'static> BehaviorRegistry<B> {
+    pub fn new() -> Self {
+        let mut registry = Self {
+            builders: HashMap::new(),
+        };
+        registry
+            .register_builder("Sequence", |de, registry| {
+                Ok(ChildrenSeed { registry }
+                    .deserialize(de)?
+                    .into_iter()
+                    .collect::<Sequence<_>>()
+                    .arc())
+            })
+            .register_builder("Selector", |de, registry| {
+                Ok(ChildrenSeed { registry }
+                    .deserialize(de)?
+                    .into_iter()
+                    .collect::<Selector<_>>()
+                    .arc())
+            })
+            .register_builder("ReactiveSequence", |de, registry| {
+                Ok(ChildrenSeed { registry }
+                    .deserialize(de)?
+                    .into_iter()
+                    .collect::<ReactiveSequence<_>>()
+                    .arc())
+            })
+            .register_builder("ReactiveSelector", |de, registry| {
+                Ok(ChildrenSeed { registry }
+                    .deserialize(de)?
+                    .into_iter()
+                    .collect::<ReactiveSelector<_>>()
+                    .arc())
+            })
+            .register_builder("Parallel", |de, registry| {
+                let mut fields =
+                    FieldsSeed::new(registry, &["policy", "children"]).deserialize(de)?;
+                Ok(fields
+                    .take_children()?
+                    .into_iter()
+                    .collect::<Parallel<_>>()
+                    .with_policy(fields.policy.unwrap_or_default())
+                    .arc())
+            })
+            .register_builder("Inverter", |de, registry| {
+                Ok(Inverter::new(NodeSeed { registry }.deserialize(de)?).arc())
+            })
+            .register_builder("Succeeder", |de, registry| {
+                Ok(Succeeder::new(NodeSeed { registry }.deserialize(de)?).arc())
+            })
+            .register_builder("Repeated", |de, registry| {
+                Ok(Repeated::new(NodeSeed { registry }.deserialize(de)?).arc())
+            })
+            .register_builder("RepeatedUntilFailure", |de, registry| {
+                Ok(RepeatedUntilFailure::new(NodeSeed { registry }.deserialize(de)?).arc())
+            })
+            .register_builder("LimitedRepeated", |de, registry| {
+                let mut fields = FieldsSeed::new(registry, &["limit", "child"]).deserialize(de)?;
+                let limit = fields
+                    .limit
+                    .ok_or_else(|| serde::de::Error::missing_field("limit"))?;
+                Ok(LimitedRepeated::new(fields.take_child()?, limit).arc())
+            });
+        registry
+    }
+
+    /// Register a node named `name`, built from parameters `P`
+    pub fn register<P, F>(&mut self, name: impl Into<String>, build: F) -> &mut Self
+    where
+        P: DeserializeOwned,
+        F: Fn(P) -> BehaviorArc<B> + Send + Sync + 'static,
+    {
+        self.register_builder(name, move |de, _| {
+            erased_serde::deserialize::<P>(de).map(&build)
+        })
+    }
+
+    /// Register a leaf node named `name` that is its own parameters
+    pub fn register_leaf<T>(&mut self, name: impl Into<String>) -> &mut Self
+    where
+        T: BehaviorNode<B> + DeserializeOwned + Send + Sync + 'static,
+    {
+        self.register(name, |leaf: T| leaf.arc())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.builders.contains_key(name)
+    }
+
+    pub fn build_from_str(&self, ron: &str) -> Result<BehaviorArc<B>, SpannedError> {
+        Self::options().from_str_seed(ron, NodeSeed { registry: self })
+    }
+
+    pub fn build_from_bytes(&self, ron: &[u8]) -> Result<BehaviorArc<B>, SpannedError> {
+        Self::options().from_bytes_seed(ron, NodeSeed { registry: self })
+    }
+
+    fn options() -> ron::Options {
+        // Lets us write `Node(field: ...)` instead of `Node((field: ...))`
+        ron::Options::default().with_default_extension(Extensions::UNWRAP_VARIANT_NEWTYPES)
+    }
+
+    fn register_builder(
+        &mut self,
+        name: impl Into<String>,
+        builder: impl for<'de> Fn(
+                &mut dyn erased_serde::Deserializer<'de>,
+                &BehaviorRegistry<B>,
+            ) -> Result<BehaviorArc<B>, erased_serde::Error>
+            + Send
+            + Sync
+            + 'static,
+    ) -> &mut Self {
+        self.builders.insert(name.into(), Box::new(builder));
+        self
+    }
+}
+
+impl<B: HasBlackboard + 'static> BehaviorRegistry<B> {
+    /// Register the nodes that need a [`Blackboard`](crate::blackboard::Blackboard)
+    pub fn register_blackboard_nodes(&mut self) -> &mut Self {
+        self.register_builder("Scoped", |de, registry| {
+            let mut fields = FieldsSeed::new(registry, &["name", "child"]).deserialize(de)?;
+            let name = fields
+                .name
+                .take()
+                .ok_or_else(|| serde::de::Error::missing_field("name"))?;
+            Ok(Scoped::new(name, fields.take_child()?).arc())
+        })
+        .register_builder("UtilitySelector", |de, registry| {
+            let mut fields =
+                FieldsSeed::new(registry, &["hysteresis", "options"]).deserialize(de)?;
+            let options = fields
+                .options
+                .take()
+                .ok_or_else(|| serde::de::Error::missing_field("options"))?;
+            let mut selector =
+                UtilitySelector::new().with_hysteresis(fields.hysteresis.unwrap_or_default());
+            for mut option in options {
+                let score = option
+                    .score
+                    .take()
+                    .ok_or_else(|| serde::de::Error::missing_field("score"))?;
+                selector =
+                    selector.with_scored_option(BlackboardKey::named(score), option.take_child()?);
+            }
+            Ok(selector.arc())
+        })
+    }
+}
+
+impl<B: HasTime + 'static> BehaviorRegistry<B> {
+    /// Register the nodes that need to know the [time](crate::time)
+    pub fn register_time_nodes(&mut self) -> &mut Self {
+        self.register_builder("Timeout", |de, registry| {
+            let mut fields = FieldsSeed::new(registry, &["seconds", "child"]).deserialize(de)?;
+            Ok(Timeout::new(fields.duration()?, fields.take_child()?).arc())
+        })
+        .register_builder("Cooldown", |de, registry| {
+            let mut fields = FieldsSeed::new(registry, &["seconds", "child"]).deserialize(de)?;
+            Ok(Cooldown::new(fields.duration()?, fields.take_child()?).arc())
+        })
+        .register_builder("Delay", |de, registry| {
+            let mut fields = FieldsSeed::new(registry, &["seconds", "child"]).deserialize(de)?;
+            Ok(Delay::new(fields.duration()?, fields.take_child()?).arc())
+        })
+        .register_builder("Wait", |de, registry| {
+            let fields = FieldsSeed::new(registry, &["seconds"]).deserialize(de)?;
+            Ok(Wait::new(fields.duration()?).arc())
+        })
+    }
+}
+
+impl<B: HasRng + 'static> BehaviorRegistry<B> {
+    /// Register the nodes that make [random](crate::random) choices
+    pub fn register_random_nodes(&mut self) -> &mut Self {
+        self.register_builder("RandomSelector", |de, registry| {
+            Ok(ChildrenSeed { registry }
+                .deserialize(de)?
+                .into_iter()
+                .collect::<RandomSelector<_>>()
+                .arc())
+        })
+        .register_builder("ShuffledSequence", |de, registry| {
+            Ok(ChildrenSeed { registry }
+                .deserialize(de)?
+                .into_iter()
+                .collect::<ShuffledSequence<_>>()
+                .arc())
+        })
+        .register_builder("WeightedChoice", |de, registry| {
+            let mut fields = FieldsSeed::new(registry, &["choices"]).deserialize(de)?;
+            let choices = fields
+                .choices
+                .take()
+                .ok_or_else(|| serde::de::Error::missing_field("choices"))?;
+            Ok(choices
+                .into_iter()
+                .map(|mut choice| {
+                    let weight = choice
+                        .weight
+                        .ok_or_else(|| serde::de::Error::missing_field("weight"))?;
+                    Ok((weight, choice.take_child()?))
+                })
+                .collect::<Result<WeightedChoice<_>, erased_serde::Error>>()?
+                .arc())
+        })
+    }
+}
+
+/// An identifier, like a node or field name
+struct Identifier(String);
+
+impl<'de> Deserialize<'de> for Identifier {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct IdentifierVisitor;
+
+        impl<'de> Visitor<'de> for IdentifierVisitor {
+            type Value = Identifier;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("an identifier")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(Identifier(v.to_string()))
+            }
+        }
+
+        deserializer.deserialize_identifier(IdentifierVisitor)
+    }
+}
+
+/// Deserializes a single node
+struct NodeSeed<'r, B> {
+    registry: &'r BehaviorRegistry<B>,
+}
+
+impl<'de, 'r, B: 'static> DeserializeSeed<'de> for NodeSeed<'r, B> {
+    type Value = BehaviorArc<B>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_enum("Node", &[], self)
+    }
+}
+
+impl<'de, 'r, B: 'static> Visitor<'de> for NodeSeed<'r, B> {
+    type Value = BehaviorArc<B>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a behavior node")
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        let (Identifier(name), variant) = data.variant()?;
+        let Some(builder) = self.registry.builders.get(&name) else {
+            return Err(serde::de::Error::custom(format!("unknown node `{name}`")));
+        };
+        variant.newtype_variant_seed(BuilderSeed {
+            builder,
+            registry: self.registry,
+        })
+    }
+}
+
+/// Deserializes the parameters of a node with its builder
+struct BuilderSeed<'r, B> {
+    builder: &'r Builder<B>,
+    registry: &'r BehaviorRegistry<B>,
+}
+
+impl<'de, 'r, B: 'static> DeserializeSeed<'de> for BuilderSeed<'r, B> {
+    type Value = BehaviorArc<B>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.builder)(&mut erased, self.registry).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Deserializes a list of nodes
+struct ChildrenSeed<'r, B> {
+    registry: &'r BehaviorRegistry<B>,
+}
+
+impl<'de, 'r, B: 'static> DeserializeSeed<'de> for ChildrenSeed<'r, B> {
+    type Value = Vec<BehaviorArc<B>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'r, B: 'static> Visitor<'de> for ChildrenSeed<'r, B> {
+    type Value = Vec<BehaviorArc<B>>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a list of behavior nodes")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut children = vec![];
+        while let Some(child) = seq.next_element_seed(NodeSeed {
+            registry: self.registry,
+        })? {
+            children.push(child);
+        }
+        Ok(children)
+    }
+}
+
+/// Deserializes a list of children that each come with some `fields`
+struct OptionsSeed<'r, B> {
+    registry: &'r BehaviorRegistry<B>,
+    fields: &'static [&'static str],
+}
+
+impl<'de, 'r, B: 'static> DeserializeSeed<'de> for OptionsSeed<'r, B> {
+    type Value = Vec<Fields<B>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'r, B: 'static> Visitor<'de> for OptionsSeed<'r, B> {
+    type Value = Vec<Fields<B>>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a list of options with the fields {:?}", self.fields)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut options = vec![];
+        while let Some(option) =
+            seq.next_element_seed(FieldsSeed::new(self.registry, self.fields))?
+        {
+            options.push(option);
+        }
+        Ok(options)
+    }
+}
+
+/// Parameters a built-in composite can take
+struct Fields<B> {
+    child: Option<BehaviorArc<B>>,
+    children: Option<Vec<BehaviorArc<B>>>,
+    limit: Option<usize>,
+    policy: Option<ParallelPolicy>,
+    name: Option<String>,
+    seconds: Option<f32>,
+    hysteresis: Option<f32>,
+    options: Option<Vec<Fields<B>>>,
+    score: Option<String>,
+    choices: Option<Vec<Fields<B>>>,
+    weight: Option<f32>,
+}
+
+impl<B> Fields<B> {
+    fn take_child<E: serde::de::Error>(&mut self) -> Result<BehaviorArc<B>, E> {
+        self.child.take().ok_or_else(|| E::missing_field("child"))
+    }
+
+    fn duration<E: serde::de::Error>(&self) -> Result<Duration, E> {
+        let seconds = self.seconds.ok_or_else(|| E::missing_field("seconds"))?;
+        Duration::try_from_secs_f32(seconds).map_err(|_| {
+            E::invalid_value(
+                serde::de::Unexpected::Float(seconds.into()),
+                &"a positive number of seconds",
+            )
+        })
+    }
+
+    fn take_children<E: serde::de::Error>(&mut self) -> Result<Vec<BehaviorArc<B>>, E> {
+        self.children
+            .take()
+            .ok_or_else(|| E::missing_field("children"))
+    }
+}
+
+/// Deserializes the `fields` of a built-in composite
+struct FieldsSeed<'r, B> {
+    registry: &'r BehaviorRegistry<B>,
+    fields: &'static [&'static str],
+}
+
+impl<'r, B> FieldsSeed<'r, B> {
+    fn new(registry: &'r BehaviorRegistry<B>, fields: &'static [&'static str]) -> Self {
+        Self { registry, fields }
+    }
+}
+
+impl<'de, 'r, B: 'static> DeserializeSeed<'de> for FieldsSeed<'r, B> {
+    type Value = Fields<B>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct("", self.fields, self)
+    }
+}
+
+impl<'de, 'r, B: 'static> Visitor<'de> for FieldsSeed<'r, B> {
+    type Value = Fields<B>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "the fields {:?}", self.fields)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut fields = Fields {
+            child: None,
+            children: None,
+            limit: None,
+            policy: None,
+            name: None,
+            seconds: None,
+            hysteresis: None,
+            options: None,
+            score: None,
+            choices: None,
+            weight: None,
+        };
+        let registry = self.registry;
+        while let Some(Identifier(key)) = map.next_key()? {
+            if !self.fields.contains(&key.as_str()) {
+                return Err(serde::de::Error::unknown_field(&key, self.fields));
+            }
+            match key.as_str() {
+                "child" => fields.child = Some(map.next_value_seed(NodeSeed { registry })?),
+                "children" => {
+                    fields.children = Some(map.next_value_seed(ChildrenSeed { registry })?)
+                }
+                "limit" => fields.limit = Some(map.next_value()?),
+                "policy" => fields.policy = Some(map.next_value()?),
+                "name" => fields.name = Some(map.next_value()?),
+                "seconds" => fields.seconds = Some(map.next_value()?),
+                "hysteresis" => fields.hysteresis = Some(map.next_value()?),
+                "options" => {
+                    fields.options = Some(map.next_value_seed(OptionsSeed {
+                        registry,
+                        fields: &["score", "child"],
+                    })?)
+                }
+                "choices" => {
+                    fields.choices = Some(map.next_value_seed(OptionsSeed {
+                        registry,
+                        fields: &["weight", "child"],
+                    })?)
+                }
+                "weight" => fields.weight = Some(map.next_value()?),
+                "score" => fields.score = Some(map.next_value()?),
+                _ => unreachable!("all composite fields are handled"),
+            }
+        }
+        Ok(fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BehaviorRegistry;
+    use crate::blackboard::{Blackboard, BlackboardKey};
+    use crate::composite::LimitedRepeated;
+    use crate::{BehaviorNode, BehaviorRunner, NodeResult};
+    use assert2::{check, let_assert};
+
+    const BARKS: BlackboardKey<Vec<String>> = BlackboardKey::new("barks");
+
+    /// Barks `sound`, taking `ticks` ticks to do so
+    #[derive(Debug, Clone, serde::Deserialize)]
+    struct Bark {
+        sound: String,
+        #[serde(default)]
+        ticks: usize,
+    }
+
+    impl BehaviorNode<Blackboard> for Bark {
+        fn tick(&self, blackboard: &mut Blackboard) -> NodeResult<Blackboard> {
+            if self.ticks > 1 {
+                return NodeResult::Running(
+                    Self {
+                        ticks: self.ticks - 1,
+                        ..self.clone()
+                    }
+                    .arc(),
+                );
+            }
+            let mut barks = blackboard.get_or_default(&BARKS);
+            barks.push(self.sound.clone());
+            blackboard.set(&BARKS, barks);
+            NodeResult::Success
+        }
+    }
+
+    /// Fails
+    #[derive(Debug, serde::Deserialize)]
+    struct Sulk;
+
+    impl BehaviorNode<Blackboard> for Sulk {
+        fn tick(&self, _blackboard: &mut Blackboard) -> NodeResult<Blackboard> {
+            NodeResult::Failure
+        }
+    }
+
+    fn registry() -> BehaviorRegistry<Blackboard> {
+        let mut registry = BehaviorRegistry::new();
+        registry
+            .register_leaf::<Bark>("Bark")
+            .register_leaf::<Sulk>("Sulk")
+            .register("Woof", |times: usize| {
+                LimitedRepeated::new(
+                    Bark {
+                        sound: "woof".to_string(),
+                        ticks: 1,
+                    }
+                    .arc(),
+                    times,
+                )
+                .arc()
+            })
+            .register_blackboard_nodes();
+        registry
+    }
+
+    fn run(ron: &str) -> (Option<bool>, Vec<String>) {
+        let tree = registry().build_from_str(ron).unwrap();
+        let mut runner = BehaviorRunner::new(tree);
+        let mut blackboard = Blackboard::new();
+        let mut res = None;
+        for _ in 0..100 {
+            res = runner.proceed(&mut blackboard);
+            if res.is_some() {
+                break;
+            }
+        }
+        (res, blackboard.get_or_default(&BARKS))
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let (res, barks) = run(r#"
+            Sequence([
+                Bark(sound: "arf", ticks: 2),
+                Selector([
+                    Sulk(),
+                    Inverter(Sulk()),
+                ]),
+                LimitedRepeated(limit: 2, child: Bark(sound: "yip")),
+                Woof(3),
+                Parallel(
+                    policy: SucceedOnOne,
+                    children: [Sulk(), Succeeder(Sulk())],
+                ),
+                Scoped(name: "quiet", child: Bark(sound: "...")),
+            ])
+        "#);
+        check!(res == Some(true));
+        check!(barks == ["arf", "yip", "yip", "woof", "woof", "woof"]);
+    }
+
+    #[test]
+    fn test_utility_selector() {
+        let tree = registry()
+            .build_from_str(
+                r#"
+                UtilitySelector(
+                    hysteresis: 0.1,
+                    options: [
+                        (score: "boredom", child: Bark(sound: "arf")),
+                        (score: "hunger", child: Bark(sound: "whine")),
+                    ],
+                )
+                "#,
+            )
+            .unwrap();
+        let mut blackboard = Blackboard::new();
+        blackboard.set(&BlackboardKey::<f32>::named("hunger"), 1.0);
+        check!(BehaviorRunner::new(tree).proceed(&mut blackboard) == Some(true));
+        check!(blackboard.get_or_default(&BARKS) == ["whine"]);
+    }
+
+    #[test]
+    fn test_unknown_node() {
+        let_assert!(Err(err) = registry().build_from_str("Sequence([\n    Meow(),\n])"));
+        check!(err.to_string().contains("unknown node `Meow`"));
+        check!(err.position.line == 2);
+    }
+
+    #[test]
+    fn test_bad_params() {
+        let_assert!(Err(err) = registry().build_from_str("Inverter(\n  Bark(volume: 11),\n)"));
+        check!(err.position.line == 2);
+
+        let_assert!(Err(err) = registry().build_from_str("LimitedRepeated(child: Sulk())"));
+        check!(err.to_string().contains("limit"));
+
+        let_assert!(Err(err) = registry().build_from_str("Sequence(Sulk())"));
+        check!(err.position.line == 1);
+    }
+}
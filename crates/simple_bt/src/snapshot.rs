@@ -0,0 +1,309 @@
+//! Save where a running tree is, and pick it up from there later
+//!
+//! A [`NodeState`] mirrors the running part of a tree: the data a continuation
+//! keeps for itself, and the states of its running children by index. States are
+//! restored against the tree they were taken from, so trees themselves are never
+//! saved. Times are saved relative to [`HasTime::elapsed`](super::time::HasTime::elapsed),
+//! so clocks don't have to survive a save either.
+//!
+//! Values on a [`Blackboard`](super::blackboard::Blackboard) are up to the game to save.
+
+use crate::{BehaviorArc, BehaviorNode, BehaviorRunner};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("`{0}` can't be saved or restored")]
+    Unsupported(String),
+    #[error("the saved state doesn't fit `{0}`, was the tree changed?")]
+    Mismatch(String),
+    #[error("bad node data: {0}")]
+    Data(String),
+}
+
+/// The saved state of a running node
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<ron::Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    children: Vec<(usize, NodeState)>,
+}
+
+impl NodeState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep `data` for the node, to be read back with [`data`](Self::data)
+    pub fn with_data(self, data: &impl Serialize) -> Result<Self, SnapshotError> {
+        let data = ron::to_string(data)
+            .map_err(|err| err.to_string())
+            .and_then(|data| ron::from_str(&data).map_err(|err| err.to_string()))
+            .map_err(SnapshotError::Data)?;
+        Ok(Self {
+            data: Some(data),
+            ..self
+        })
+    }
+
+    /// Add the state of the running child at `index`
+    pub fn with_child(mut self, index: usize, state: NodeState) -> Self {
+        self.children.push((index, state));
+        self
+    }
+
+    pub fn data<T: DeserializeOwned>(&self) -> Result<T, SnapshotError> {
+        self.data
+            .clone()
+            .unwrap_or(ron::Value::Unit)
+            .into_rust()
+            .map_err(|err| SnapshotError::Data(err.to_string()))
+    }
+
+    /// The running children, by index
+    pub fn children(&self) -> &[(usize, NodeState)] {
+        &self.children
+    }
+
+    /// The state of the running child at `index`
+    pub fn child(&self, index: usize) -> Option<&NodeState> {
+        self.children
+            .iter()
+            .find(|(idx, _)| *idx == index)
+            .map(|(_, state)| state)
+    }
+
+    /// The only running child, for nodes that run one child at a time
+    pub fn only_child(&self) -> Option<(usize, &NodeState)> {
+        match self.children.as_slice() {
+            [(index, state)] => Some((*index, state)),
+            _ => None,
+        }
+    }
+}
+
+/// Restore the `index`th of `children` of `parent` from `state`
+pub(crate) fn restore_child<B>(
+    parent: &dyn BehaviorNode<B>,
+    children: &[BehaviorArc<B>],
+    index: usize,
+    state: &NodeState,
+    context: &B,
+) -> Result<BehaviorArc<B>, SnapshotError> {
+    children
+        .get(index)
+        .ok_or_else(|| mismatch(parent))?
+        .restore(state, context)
+}
+
+/// Restore the only running child of `parent` from `state`, if it has one
+pub(crate) fn restore_only_child<B>(
+    parent: &dyn BehaviorNode<B>,
+    children: &[BehaviorArc<B>],
+    state: &NodeState,
+    context: &B,
+) -> Result<Option<(usize, BehaviorArc<B>)>, SnapshotError> {
+    if state.children().is_empty() {
+        return Ok(None);
+    }
+    let (index, child) = state.only_child().ok_or_else(|| mismatch(parent))?;
+    Ok(Some((
+        index,
+        restore_child(parent, children, index, child, context)?,
+    )))
+}
+
+/// Save a decorator, with its running child at index 0 if it has one
+pub(crate) fn snapshot_decorated<B>(
+    resume: Option<&BehaviorArc<B>>,
+    context: &B,
+) -> Result<NodeState, SnapshotError> {
+    Ok(match resume {
+        Some(resume) => NodeState::new().with_child(0, resume.snapshot(context)?),
+        None => NodeState::new(),
+    })
+}
+
+/// Restore the running child of a decorator, which is saved at index 0
+pub(crate) fn restore_decorated<B>(
+    parent: &dyn BehaviorNode<B>,
+    child: &BehaviorArc<B>,
+    state: &NodeState,
+    context: &B,
+) -> Result<BehaviorArc<B>, SnapshotError> {
+    let state = state.child(0).ok_or_else(|| mismatch(parent))?;
+    child.restore(state, context)
+}
+
+pub(crate) fn mismatch<B>(node: &dyn BehaviorNode<B>) -> SnapshotError {
+    SnapshotError::Mismatch(node.name().into_owned())
+}
+
+/// Where a [`BehaviorRunner`] is in its tree
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunnerState {
+    /// `None` if the tree isn't running
+    pub running: Option<NodeState>,
+}
+
+impl<B> BehaviorRunner<B> {
+    /// Save where the tree is, to [`restore`](Self::restore) later
+    pub fn snapshot(&self, context: &B) -> Result<RunnerState, SnapshotError> {
+        Ok(RunnerState {
+            running: self
+                .current()
+                .map(|current| current.snapshot(context))
+                .transpose()?,
+        })
+    }
+
+    /// Run `tree` from where `state` was saved
+    ///
+    /// `tree` must be the tree the state was saved from.
+    pub fn restore(
+        tree: BehaviorArc<B>,
+        state: &RunnerState,
+        context: &B,
+    ) -> Result<Self, SnapshotError> {
+        let current = state
+            .running
+            .as_ref()
+            .map(|running| tree.restore(running, context))
+            .transpose()?;
+        let mut runner = Self::new(tree);
+        runner.current_tick = current;
+        Ok(runner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RunnerState, SnapshotError};
+    use crate::composite::{LimitedRepeated, Parallel, Sequence, Timeout, Wait};
+    use crate::inspect::NodeStatus;
+    use crate::leaf::Action;
+    use crate::time::{HasTime, ManualClock};
+    use crate::{BehaviorArc, BehaviorNode, BehaviorRunner, NodeResult};
+    use assert2::{check, let_assert};
+    use std::time::Duration;
+
+    #[derive(Debug, Default)]
+    struct Context {
+        clock: ManualClock,
+        log: Vec<&'static str>,
+    }
+
+    impl HasTime for Context {
+        fn elapsed(&self) -> Duration {
+            self.clock.elapsed()
+        }
+    }
+
+    fn say(word: &'static str) -> BehaviorArc<Context> {
+        Action::from_fn(move |context: &mut Context| {
+            context.log.push(word);
+            NodeStatus::Success
+        })
+        .arc()
+    }
+
+    fn patrol() -> BehaviorArc<Context> {
+        let walk = [
+            say("left"),
+            Wait::new(Duration::from_secs(2)).arc(),
+            say("right"),
+        ]
+        .into_iter()
+        .collect::<Sequence<_>>()
+        .arc();
+        let watch = Timeout::new(
+            Duration::from_secs(10),
+            Wait::new(Duration::from_secs(60)).arc(),
+        )
+        .arc();
+        [LimitedRepeated::new(walk, 3).arc(), watch]
+            .into_iter()
+            .collect::<Parallel<_>>()
+            .arc()
+    }
+
+    /// Advance a second, and tick
+    fn step(runner: &mut BehaviorRunner<Context>, context: &mut Context) -> Option<bool> {
+        context.clock.advance_secs(1.0);
+        runner.proceed(context)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tree = patrol();
+        let mut context = Context::default();
+        let mut runner = BehaviorRunner::new(tree.clone());
+        for _ in 0..4 {
+            check!(step(&mut runner, &mut context).is_none());
+        }
+
+        let state = ron::to_string(&runner.snapshot(&context).unwrap()).unwrap();
+        let state: RunnerState = ron::from_str(&state).unwrap();
+        // A fresh clock, as after loading a game
+        let mut loaded = Context::default();
+        let mut restored = BehaviorRunner::restore(tree, &state, &loaded).unwrap();
+
+        context.log.clear();
+        loop {
+            let result = step(&mut runner, &mut context);
+            check!(step(&mut restored, &mut loaded) == result);
+            if result.is_some() {
+                break;
+            }
+        }
+        check!(loaded.log == context.log);
+        check!(loaded.log == ["right", "left", "right"]);
+    }
+
+    #[test]
+    fn test_idle() {
+        let runner = BehaviorRunner::new(patrol());
+        let state = runner.snapshot(&Context::default()).unwrap();
+        check!(state.running.is_none());
+        let restored = BehaviorRunner::restore(patrol(), &state, &Context::default()).unwrap();
+        check!(!restored.is_running());
+    }
+
+    /// Runs forever, without saving
+    #[derive(Debug)]
+    struct Dream;
+
+    impl BehaviorNode<Context> for Dream {
+        fn tick(&self, _: &mut Context) -> NodeResult<Context> {
+            NodeResult::Running(Dream.arc())
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut context = Context::default();
+        let tree = [say("zzz"), Dream.arc()]
+            .into_iter()
+            .collect::<Sequence<_>>()
+            .arc();
+        let mut runner = BehaviorRunner::new(tree);
+        check!(runner.proceed(&mut context).is_none());
+        let_assert!(Err(SnapshotError::Unsupported(name)) = runner.snapshot(&context));
+        check!(name == "Dream");
+
+        // States only fit the tree they came from
+        let mut runner = BehaviorRunner::new(patrol());
+        check!(runner.proceed(&mut context).is_none());
+        let state = runner.snapshot(&context).unwrap();
+        let other = [say("left"), say("right")]
+            .into_iter()
+            .collect::<Parallel<_>>()
+            .arc();
+        let_assert!(
+            Err(SnapshotError::Mismatch(_)) = BehaviorRunner::restore(other, &state, &context)
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Leaves that wait on futures
+//!
+//! Work that shouldn't block a frame, like a long path search, is started as an
+//! [`AsyncTask`] on the [`AsyncComputeTaskPool`], and the leaf keeps running until
+//! it finishes.
+
+use crate::snapshot::{mismatch, NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, NodeResult};
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use bevy_async_task::{AsyncReceiver, AsyncTask};
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex, PoisonError};
+
+type Start<B, T> = Arc<dyn Fn(&mut B) -> AsyncTask<T> + Send + Sync>;
+type Finish<B, T> = Arc<dyn Fn(T, &mut B) -> bool + Send + Sync>;
+
+/// A task in flight
+///
+/// Dropping the [`Task`] cancels it, so a task nobody waits for anymore stops
+/// at its next await point.
+struct InFlight<T> {
+    _task: Task<()>,
+    receiver: AsyncReceiver<T>,
+}
+
+/// Starts a task when ticked, and runs until it finishes
+///
+/// The output of the task is handed to `finish` along with the context, which
+/// decides if the leaf succeeds. Halting the leaf cancels the task.
+///
+/// Tasks can't be saved, so a restored leaf starts its task over.
+pub struct AsyncAction<B, T> {
+    name: Cow<'static, str>,
+    start: Start<B, T>,
+    finish: Finish<B, T>,
+    /// Set once the task is started, and taken when it is halted
+    in_flight: Option<Arc<Mutex<Option<InFlight<T>>>>>,
+}
+
+impl<B, T> std::fmt::Debug for AsyncAction<B, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncAction")
+            .field("name", &self.name)
+            .field("started", &self.in_flight.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B, T> AsyncAction<B, T> {
+    /// Start tasks with `start`, and complete with what `finish` makes of their output
+    pub fn new(
+        name: impl Into<Cow<'static, str>>,
+        start: impl Fn(&mut B) -> AsyncTask<T> + Send + Sync + 'static,
+        finish: impl Fn(T, &mut B) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            start: Arc::new(start),
+            finish: Arc::new(finish),
+            in_flight: None,
+        }
+    }
+}
+
+impl<B> AsyncAction<B, bool> {
+    /// Start tasks with `start`, succeeding if they return `true`
+    pub fn from_task(
+        name: impl Into<Cow<'static, str>>,
+        start: impl Fn(&mut B) -> AsyncTask<bool> + Send + Sync + 'static,
+    ) -> Self {
+        Self::new(name, start, |success, _| success)
+    }
+}
+
+impl<B: 'static, T: Send + 'static> BehaviorNode<B> for AsyncAction<B, T> {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        let in_flight = match self.in_flight.as_ref() {
+            Some(in_flight) => in_flight.clone(),
+            None => {
+                let (fut, receiver) = (self.start)(context).into_parts();
+                Arc::new(Mutex::new(Some(InFlight {
+                    _task: AsyncComputeTaskPool::get().spawn(fut),
+                    receiver,
+                })))
+            }
+        };
+        let output = in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_mut()
+            .and_then(|in_flight| in_flight.receiver.try_recv());
+        match output {
+            Some(output) => {
+                if (self.finish)(output, context) {
+                    NodeResult::Success
+                } else {
+                    NodeResult::Failure
+                }
+            }
+            None => NodeResult::Running(
+                Self {
+                    name: self.name.clone(),
+                    start: self.start.clone(),
+                    finish: self.finish.clone(),
+                    in_flight: Some(in_flight),
+                }
+                .arc(),
+            ),
+        }
+    }
+
+    fn halt(&self, _context: &mut B) {
+        if let Some(in_flight) = self.in_flight.as_ref() {
+            in_flight
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+        }
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.name.clone()
+    }
+
+    fn snapshot(&self, _context: &B) -> Result<NodeState, SnapshotError> {
+        Ok(NodeState::new())
+    }
+
+    fn restore(&self, state: &NodeState, _context: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        if *state != NodeState::new() {
+            return Err(mismatch(self));
+        }
+        Ok(Self {
+            name: self.name.clone(),
+            start: self.start.clone(),
+            finish: self.finish.clone(),
+            in_flight: None,
+        }
+        .arc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncAction;
+    use crate::{BehaviorNode, BehaviorRunner};
+    use assert2::check;
+    use bevy::tasks::{AsyncComputeTaskPool, TaskPool};
+    use bevy_async_task::AsyncTask;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    /// Keep ticking until `runner` completes, or a second has passed
+    fn run(runner: &mut BehaviorRunner<Vec<u32>>, context: &mut Vec<u32>) -> Option<bool> {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(1) {
+            if let Some(result) = runner.proceed(context) {
+                return Some(result);
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        None
+    }
+
+    #[test]
+    fn test_finishes() {
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        let tree = AsyncAction::new(
+            "Search",
+            |context: &mut Vec<u32>| {
+                let from = context.len() as u32;
+                AsyncTask::new(async move { from + 1 })
+            },
+            |found, context| {
+                context.push(found);
+                found < 3
+            },
+        )
+        .arc();
+        let mut context = vec![];
+        let mut runner = BehaviorRunner::new(tree);
+        check!(run(&mut runner, &mut context) == Some(true));
+        check!(context == [1]);
+
+        // Every run starts a new task
+        check!(run(&mut runner, &mut context) == Some(true));
+        check!(run(&mut runner, &mut context) == Some(false));
+        check!(context == [1, 2, 3]);
+    }
+
+    /// Sets its flag when dropped
+    struct Dropped(Arc<AtomicBool>);
+
+    impl Drop for Dropped {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_halt_cancels() {
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = cancelled.clone();
+        let tree = AsyncAction::from_task("Forever", move |_: &mut Vec<u32>| {
+            let dropped = Dropped(flag.clone());
+            AsyncTask::new(async move {
+                let _dropped = dropped;
+                std::future::pending::<bool>().await
+            })
+        })
+        .arc();
+        let mut context = vec![];
+        let mut runner = BehaviorRunner::new(tree);
+        check!(runner.proceed(&mut context).is_none());
+        check!(runner.proceed(&mut context).is_none());
+        check!(!cancelled.load(Ordering::SeqCst));
+
+        runner.halt(&mut context);
+        let start = Instant::now();
+        while !cancelled.load(Ordering::SeqCst) && start.elapsed() < Duration::from_secs(1) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        check!(cancelled.load(Ordering::SeqCst));
+    }
+}
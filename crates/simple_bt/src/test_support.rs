@@ -0,0 +1,129 @@
+//! Fixtures shared by the unit tests
+
+use crate::snapshot::{NodeState, SnapshotError};
+use crate::{BehaviorArc, BehaviorNode, BehaviorRunner, NodeResult};
+use std::borrow::Cow;
+
+/// Logged by [`After`] when halted, plus its id
+pub(crate) const HALTED: usize = 100;
+
+/// What [`After`] records in the context it's ticked with
+pub(crate) trait Record {
+    fn ticked(&mut self, _id: usize) {}
+    fn halted(&mut self, _id: usize) {}
+}
+
+impl Record for () {}
+
+/// Counts ticks
+impl Record for usize {
+    fn ticked(&mut self, _: usize) {
+        *self += 1;
+    }
+}
+
+/// Logs ids, or `HALTED + id` when halted
+impl Record for Vec<usize> {
+    fn ticked(&mut self, id: usize) {
+        self.push(id);
+    }
+
+    fn halted(&mut self, id: usize) {
+        self.push(HALTED + id);
+    }
+}
+
+/// Records its id, then returns `result` after `ticks` ticks
+#[derive(Debug, Clone)]
+pub(crate) struct After {
+    pub id: usize,
+    pub ticks: usize,
+    pub result: bool,
+}
+
+impl After {
+    pub fn new(ticks: usize, result: bool) -> Self {
+        Self {
+            id: 0,
+            ticks,
+            result,
+        }
+    }
+
+    pub fn succeed(ticks: usize) -> Self {
+        Self::new(ticks, true)
+    }
+
+    pub fn fail(ticks: usize) -> Self {
+        Self::new(ticks, false)
+    }
+
+    pub fn with_id(self, id: usize) -> Self {
+        Self { id, ..self }
+    }
+}
+
+impl<B: Record> BehaviorNode<B> for After {
+    fn tick(&self, context: &mut B) -> NodeResult<B> {
+        context.ticked(self.id);
+        if self.ticks > 1 {
+            NodeResult::Running(
+                Self {
+                    ticks: self.ticks - 1,
+                    ..self.clone()
+                }
+                .arc(),
+            )
+        } else if self.result {
+            NodeResult::Success
+        } else {
+            NodeResult::Failure
+        }
+    }
+
+    fn halt(&self, context: &mut B) {
+        context.halted(self.id);
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        format!("After({})", self.ticks).into()
+    }
+
+    fn snapshot(&self, _: &B) -> Result<NodeState, SnapshotError> {
+        NodeState::new().with_data(&self.ticks)
+    }
+
+    fn restore(&self, state: &NodeState, _: &B) -> Result<BehaviorArc<B>, SnapshotError> {
+        Ok(Self {
+            ticks: state.data()?,
+            ..self.clone()
+        }
+        .arc())
+    }
+}
+
+/// Proceed with `tree` until it completes
+pub(crate) fn run<B>(tree: BehaviorArc<B>, context: &mut B) -> bool {
+    let mut runner = BehaviorRunner::new(tree);
+    loop {
+        if let Some(result) = runner.proceed(context) {
+            return result;
+        }
+    }
+}
+
+/// Proceed with `tree` for at most `max_ticks` ticks, returning its result if it
+/// completed, and how many ticks that took
+pub(crate) fn run_for<B>(
+    tree: BehaviorArc<B>,
+    context: &mut B,
+    max_ticks: usize,
+) -> (Option<bool>, usize) {
+    let mut runner = BehaviorRunner::new(tree);
+    for ticks in 1..=max_ticks {
+        if let Some(result) = runner.proceed(context) {
+            return (Some(result), ticks);
+        }
+    }
+    (None, max_ticks)
+}
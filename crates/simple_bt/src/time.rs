@@ -0,0 +1,41 @@
+//! Let nodes know what time it is
+//!
+//! Nodes that wait for something, like [`Wait`](super::composite::Wait), read the time
+//! from their context through [`HasTime`].
+
+use std::time::Duration;
+
+/// Contexts that know how much time has passed
+pub trait HasTime {
+    /// Time elapsed since some fixed point
+    ///
+    /// Only differences between readings matter, but it must never go backwards.
+    fn elapsed(&self) -> Duration;
+}
+
+/// A clock that only moves when told to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy", derive(bevy::reflect::Reflect))]
+pub struct ManualClock {
+    elapsed: Duration,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&mut self, by: Duration) {
+        self.elapsed += by;
+    }
+
+    pub fn advance_secs(&mut self, secs: f32) {
+        self.advance(Duration::from_secs_f32(secs));
+    }
+}
+
+impl HasTime for ManualClock {
+    fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
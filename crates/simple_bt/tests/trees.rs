@@ -0,0 +1,179 @@
+//! Trees as another crate would use them, without Bevy
+
+use assert2::{check, let_assert};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use serde::Deserialize;
+use simple_bt::blackboard::{Blackboard, BlackboardKey, HasBlackboard};
+use simple_bt::composite::Wait;
+use simple_bt::export::Diagram;
+use simple_bt::inspect::NodeStatus;
+use simple_bt::leaf::{Action, Condition};
+use simple_bt::random::HasRng;
+use simple_bt::registry::BehaviorRegistry;
+use simple_bt::snapshot::RunnerState;
+use simple_bt::time::{HasTime, ManualClock};
+use simple_bt::{sel, seq, BehaviorArc, BehaviorNode, BehaviorRunner, NodeResult};
+use std::time::Duration;
+
+const ALERT: BlackboardKey<bool> = BlackboardKey::new("alert");
+
+/// A guard on patrol
+struct Guard {
+    clock: ManualClock,
+    rng: StdRng,
+    blackboard: Blackboard,
+    position: i32,
+}
+
+impl Guard {
+    fn new() -> Self {
+        Self {
+            clock: ManualClock::new(),
+            rng: StdRng::seed_from_u64(7),
+            blackboard: Blackboard::new(),
+            position: 0,
+        }
+    }
+
+    /// Advance a second, and think
+    fn step(&mut self, runner: &mut BehaviorRunner<Guard>) -> Option<bool> {
+        self.clock.advance_secs(1.0);
+        runner.proceed(self)
+    }
+}
+
+impl HasBlackboard for Guard {
+    fn blackboard(&self) -> &Blackboard {
+        &self.blackboard
+    }
+
+    fn blackboard_mut(&mut self) -> &mut Blackboard {
+        &mut self.blackboard
+    }
+}
+
+impl HasTime for Guard {
+    fn elapsed(&self) -> Duration {
+        self.clock.elapsed()
+    }
+}
+
+impl HasRng for Guard {
+    fn rng(&mut self) -> &mut dyn RngCore {
+        &mut self.rng
+    }
+}
+
+/// Walks one step at a time to `to`
+#[derive(Debug, Deserialize)]
+struct Walk {
+    to: i32,
+}
+
+impl BehaviorNode<Guard> for Walk {
+    fn tick(&self, guard: &mut Guard) -> NodeResult<Guard> {
+        guard.position += (self.to - guard.position).signum();
+        if guard.position == self.to {
+            NodeResult::Success
+        } else {
+            NodeResult::Running(Walk { to: self.to }.arc())
+        }
+    }
+}
+
+fn walk(to: i32) -> Action<Guard> {
+    Action::from_fn(move |guard: &mut Guard| {
+        guard.position += (to - guard.position).signum();
+        if guard.position == to {
+            NodeStatus::Success
+        } else {
+            NodeStatus::Running
+        }
+    })
+    .with_name(format!("Walk({to})"))
+}
+
+fn patrol() -> BehaviorArc<Guard> {
+    let alert = Condition::from_fn(|guard: &Guard| guard.blackboard.get_or_default(&ALERT));
+    sel![
+        seq![alert, walk(0)],
+        seq![walk(2), Wait::new(Duration::from_secs(1)), walk(-2)],
+    ]
+    .arc()
+}
+
+#[test]
+fn test_patrol() {
+    let mut guard = Guard::new();
+    let mut runner = BehaviorRunner::new(patrol()).with_trace(4).with_profile();
+    let mut positions = vec![];
+    while guard.step(&mut runner).is_none() {
+        positions.push(guard.position);
+    }
+    check!(positions == [1, 2, 1, 0, -1]);
+    check!(guard.position == -2);
+
+    // Raising the alarm sends the guard back to their post
+    guard.blackboard_mut().set(&ALERT, true);
+    while guard.step(&mut runner).is_none() {}
+    check!(guard.position == 0);
+
+    let profile = runner.profile().unwrap();
+    check!(profile.node("Walk").unwrap().successes == 3);
+    let diagram = Diagram::new(runner.tree())
+        .with_trace(runner.trace().unwrap())
+        .to_mermaid();
+    check!(diagram.contains("Walk(0)"));
+}
+
+#[test]
+fn test_save_and_load() {
+    let tree = patrol();
+    let mut guard = Guard::new();
+    let mut runner = BehaviorRunner::new(tree.clone());
+    for _ in 0..3 {
+        guard.step(&mut runner);
+    }
+
+    let saved = ron::to_string(&runner.snapshot(&guard).unwrap()).unwrap();
+    let state: RunnerState = ron::from_str(&saved).unwrap();
+    let mut loaded = Guard {
+        position: guard.position,
+        ..Guard::new()
+    };
+    let mut restored = BehaviorRunner::restore(tree, &state, &loaded).unwrap();
+    loop {
+        let result = guard.step(&mut runner);
+        check!(loaded.step(&mut restored) == result);
+        check!(loaded.position == guard.position);
+        if result.is_some() {
+            break;
+        }
+    }
+}
+
+#[test]
+fn test_registry() {
+    let mut registry = BehaviorRegistry::<Guard>::new();
+    registry
+        .register_time_nodes()
+        .register_random_nodes()
+        .register_leaf::<Walk>("Walk");
+    let tree = registry
+        .build_from_str(
+            "Sequence([
+                Walk(to: 3),
+                Wait(seconds: 1.0),
+                RandomSelector([Walk(to: 4), Walk(to: 2)]),
+            ])",
+        )
+        .unwrap();
+    let mut guard = Guard::new();
+    let mut runner = BehaviorRunner::new(tree);
+    while guard.step(&mut runner).is_none() {}
+    check!([2, 4].contains(&guard.position));
+
+    let_assert!(Err(err) = registry.build_from_str("Sequence([Run(to: 1)])"));
+    check!(err.to_string().contains("Run"));
+}
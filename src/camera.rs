@@ -5,6 +5,7 @@ use super::{post_process, LOGICAL_HEIGHT, LOGICAL_WIDTH};
 use bevy::prelude::*;
 use bevy_smooth_pixel_camera::components::PixelCamera;
 use bevy_smooth_pixel_camera::viewport::ViewportSize;
+use fundsp_kira::spatial::AudioListener;
 
 pub struct CameraPlugin;
 
@@ -66,6 +67,7 @@ struct CameraBundle {
     camera: Camera,
     camera_2d: Camera2dBundle,
     pixel_camera: PixelCamera,
+    listener: AudioListener,
 
     chroma_aberration: post_process::ChromaticAberattionSettings,
 }
@@ -78,6 +80,7 @@ fn setup_camera(mut commands: Commands) {
             min_width: LOGICAL_WIDTH,
             min_height: LOGICAL_HEIGHT,
         }),
+        listener: AudioListener::flat(),
         chroma_aberration: post_process::ChromaticAberattionSettings {
             // intensity: 0.02,
             ..default()
@@ -1,9 +1,7 @@
-use crate::simple_bt::composite::{Repeated, Sequence};
-use crate::simple_bt::{BehaviorNode, BehaviorRunner, NodeResult};
 use crate::utils::lerp_mix;
 use crate::{camera, collision, movement, movement_pointer, GameState};
-use crate::{fundsp_kira, player::PlayerAction, You};
 use crate::{player::DashState, player::Player};
+use crate::{player::PlayerAction, You};
 
 use bevy::prelude::*;
 use bevy::sprite::MaterialMesh2dBundle;
@@ -11,15 +9,23 @@ use bevy_asset_loader::prelude::*;
 use bevy_egui::{egui, EguiContexts};
 use leafwing_input_manager::prelude::*;
 use moonshine_spawn::spawn_children;
+use simple_bt::asset::{BehaviorTreeApp, BehaviorTreeSource};
+use simple_bt::export::Diagram;
+use simple_bt::inspect::{NodeStatus, Trace};
+use simple_bt::plugin::{BehaviorFinished, BehaviorTree, BehaviorTreeSet, EntityContext};
+use simple_bt::snapshot::{NodeState, SnapshotError};
+use simple_bt::{BehaviorArc, BehaviorNode, NodeResult};
 
 pub struct DebugPlugin;
 
 impl Plugin for DebugPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<MakeABoxTrigger>()
+            .register_behavior_leaf::<EntityContext, MoveTo>("MoveTo")
             .add_systems(Startup, setup_debug)
             .add_systems(Update, make_a_box)
-            .add_systems(Update, make_polly_think.before(movement::Movement))
+            .configure_sets(Update, BehaviorTreeSet.before(movement::Movement))
+            .add_systems(Update, report_polly_thoughts.after(BehaviorTreeSet))
             .add_systems(Update, debug_view_window.run_if(in_state(GameState::InRun)));
     }
 
@@ -35,43 +41,22 @@ impl Plugin for DebugPlugin {
 /// Your worst nightmare
 #[derive(Component)]
 struct Polly;
-#[derive(Component)]
-struct PollyBrain {
-    thinking: bool,
-    runner: BehaviorRunner<(Vec2, Vec2)>,
-}
 
 // Make Polly, your *nightmare*
 fn setup_debug(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    asset_server: Res<AssetServer>,
 ) {
     commands.spawn((
         Polly,
-        PollyBrain {
-            thinking: false,
-            runner: BehaviorRunner::new(
-                Repeated::new(
-                    [
-                        MoveTo {
-                            speed: 250.,
-                            goal: Vec2::X * 300.0,
-                        }
-                        .arc(),
-                        MoveTo {
-                            speed: 250.,
-                            goal: Vec2::NEG_X * 300.0,
-                        }
-                        .arc(),
-                    ]
-                    .into_iter()
-                    .collect::<Sequence<_>>()
-                    .arc(),
-                )
-                .arc(),
-            ),
-        },
+        // Polly thinks when told to, see `polly.bt.ron`
+        BehaviorTree::default()
+            .with_active(false)
+            .with_trace(16)
+            .with_profile(),
+        BehaviorTreeSource(asset_server.load("polly.bt.ron")),
         movement_pointer::MovementDirection(Vec2::ZERO, 0.0),
         MaterialMesh2dBundle {
             mesh: meshes
@@ -98,19 +83,11 @@ fn setup_debug(
     ));
 }
 
-fn make_polly_think(
-    mut query: Query<(&mut movement::Movable, &Transform, &mut PollyBrain), With<Polly>>,
-) {
-    let Ok((mut movable, transform, mut thunk)) = query.get_single_mut() else {
-        return;
-    };
-
-    if thunk.thinking {
-        let mut context = (transform.translation.truncate(), movable.velocity);
-        if let Some(res) = thunk.runner.proceed(&mut context) {
-            info!("Thunking complete step: {context:?} {res}");
+fn report_polly_thoughts(mut reader: EventReader<BehaviorFinished>, polly: Query<(), With<Polly>>) {
+    for finished in reader.read() {
+        if polly.contains(finished.entity) {
+            info!("Thunking complete step: {}", finished.success);
         }
-        movable.velocity = context.1;
     }
 }
 
@@ -173,20 +150,27 @@ struct DebugAssets {
     debug_sfxr: Vec<Handle<fundsp_kira::Machine>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Deserialize)]
 struct MoveTo {
     speed: f32,
     goal: Vec2,
 }
 
-impl BehaviorNode<(Vec2, Vec2)> for MoveTo {
-    fn tick(&self, (position, velocity): &mut (Vec2, Vec2)) -> NodeResult<(Vec2, Vec2)> {
-        let dist = (self.goal - *position).length();
+impl BehaviorNode<EntityContext> for MoveTo {
+    fn tick(&self, context: &mut EntityContext) -> NodeResult<EntityContext> {
+        let Some(position) = context.get::<Transform>().map(|t| t.translation.truncate()) else {
+            return NodeResult::Failure;
+        };
+        let dist = (self.goal - position).length();
         const ERROR: f32 = 10.0;
         if dist <= ERROR {
             NodeResult::Success
         } else {
-            let desired_vel = (self.goal - *position).normalize_or_zero() * self.speed;
+            let Some(mut movable) = context.get_mut::<movement::Movable>() else {
+                return NodeResult::Failure;
+            };
+            let velocity = &mut movable.velocity;
+            let desired_vel = (self.goal - position).normalize_or_zero() * self.speed;
             debug!("{desired_vel:?} {velocity:?}");
             let dvn = desired_vel.normalize_or_zero();
             let vn = velocity.normalize_or_zero();
@@ -200,6 +184,18 @@ impl BehaviorNode<(Vec2, Vec2)> for MoveTo {
             NodeResult::Running(self.clone().arc())
         }
     }
+
+    fn snapshot(&self, _context: &EntityContext) -> Result<NodeState, SnapshotError> {
+        Ok(NodeState::new())
+    }
+
+    fn restore(
+        &self,
+        _state: &NodeState,
+        _context: &EntityContext,
+    ) -> Result<BehaviorArc<EntityContext>, SnapshotError> {
+        Ok(self.clone().arc())
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -218,7 +214,7 @@ fn debug_view_window(
             Entity,
             Option<&camera::CameraTarget>,
             Option<&mut movement::Movable>,
-            &mut PollyBrain,
+            &mut BehaviorTree,
         ),
         With<Polly>,
     >,
@@ -296,17 +292,78 @@ fn debug_view_window(
                 });
             }
 
-            if ui
-                .toggle_value(&mut thunk.thinking, "Has Thought")
-                .changed()
-            {
-                if movable.is_none() && thunk.thinking {
+            if ui.toggle_value(&mut thunk.active, "Has Thought").changed() {
+                if movable.is_none() && thunk.active {
                     commands.entity(polly_entity).insert(movement::Movable {
                         mass: 20.0,
                         ..default()
                     });
                 }
             }
+
+            ui.collapsing("Thoughts", |ui| {
+                let Some(runner) = thunk.runner() else {
+                    ui.label("Polly has no thoughts.");
+                    return;
+                };
+                let Some(trace) = runner.trace() else {
+                    ui.label("Polly's thoughts are private.");
+                    return;
+                };
+                ui.label(format!("Thought {} times", trace.ticks()));
+                ui.horizontal(|ui| {
+                    let diagram = Diagram::new(runner.tree()).with_trace(trace);
+                    if ui.button("Copy as DOT").clicked() {
+                        ui.output_mut(|o| o.copied_text = diagram.to_dot());
+                    }
+                    if ui.button("Copy as Mermaid").clicked() {
+                        ui.output_mut(|o| o.copied_text = diagram.to_mermaid());
+                    }
+                });
+                behavior_tree_ui(ui, runner.tree(), &mut vec![], trace);
+            });
+
+            ui.collapsing("Effort", |ui| {
+                let Some(profile) = thunk.runner().and_then(|runner| runner.profile()) else {
+                    ui.label("Polly isn't keeping count.");
+                    return;
+                };
+                ui.label(format!(
+                    "Thought {} times in {:.2?}",
+                    profile.proceeds(),
+                    profile.time()
+                ));
+                ui.label(format!(
+                    "{:.1} nodes per thought, {} at most",
+                    profile.mean_nodes_ticked(),
+                    profile.most_nodes_ticked()
+                ));
+                ui.label(format!(
+                    "Kept thinking {} times in a row",
+                    profile.longest_running_streak()
+                ));
+                if ui.button("Copy profile as CSV").clicked() {
+                    ui.output_mut(|o| o.copied_text = profile.to_csv());
+                }
+                egui::Grid::new("polly_profile")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in ["Node", "Ticks", "Success", "Own time"] {
+                            ui.strong(header);
+                        }
+                        ui.end_row();
+                        for (name, stats) in profile.nodes() {
+                            ui.label(name);
+                            ui.label(stats.ticks.to_string());
+                            ui.label(match stats.success_ratio() {
+                                Some(ratio) => format!("{:.0}%", ratio * 100.0),
+                                None => "-".to_owned(),
+                            });
+                            ui.label(format!("{:.2?}", stats.own_time));
+                            ui.end_row();
+                        }
+                    });
+            });
         });
 
         ui.collapsing("Debug Sfxr", |ui| {
@@ -315,7 +372,7 @@ fn debug_view_window(
                 .as_ref()
                 .and_then(|hnd| machines.get(hnd.clone()))
                 .and_then(|m| m.userdata())
-                .and_then(|ud| ud.downcast_ref::<crate::sfxr::Sfxr>())
+                .and_then(|ud| ud.downcast_ref::<fundsp_kira::sfxr::Sfxr>())
             {
                 ui.label(format!("{sfxr:#?}"));
             } else {
@@ -437,3 +494,67 @@ fn debug_view_window(
         }
     });
 }
+
+/// Draw `node` and everything under it, with the last few results of each
+///
+/// Nodes that are running right now are highlighted.
+fn behavior_tree_ui(
+    ui: &mut egui::Ui,
+    node: &BehaviorArc<EntityContext>,
+    path: &mut Vec<usize>,
+    trace: &Trace,
+) {
+    use egui::{text::LayoutJob, Color32, FontId, Stroke, TextFormat};
+
+    let running = trace.is_running(path);
+    let mut job = LayoutJob::default();
+    job.append(
+        &node.name(),
+        0.0,
+        TextFormat {
+            color: if running {
+                Color32::YELLOW
+            } else {
+                ui.visuals().text_color()
+            },
+            underline: if running {
+                Stroke::new(1.0, Color32::YELLOW)
+            } else {
+                Stroke::NONE
+            },
+            ..default()
+        },
+    );
+    for (i, (_, status)) in trace.history(path).enumerate() {
+        let (text, color) = match status {
+            NodeStatus::Running => ("R", Color32::YELLOW),
+            NodeStatus::Success => ("S", Color32::GREEN),
+            NodeStatus::Failure => ("F", Color32::RED),
+        };
+        job.append(
+            text,
+            if i == 0 { 8.0 } else { 0.0 },
+            TextFormat {
+                font_id: FontId::monospace(12.0),
+                color,
+                ..default()
+            },
+        );
+    }
+
+    let children = node.children();
+    if children.is_empty() {
+        ui.label(job);
+        return;
+    }
+    egui::CollapsingHeader::new(job)
+        .id_source(&*path)
+        .default_open(true)
+        .show(ui, |ui| {
+            for (index, child) in children.iter().enumerate() {
+                path.push(index);
+                behavior_tree_ui(ui, child, path, trace);
+                path.pop();
+            }
+        });
+}
@@ -5,13 +5,10 @@ mod camera;
 mod collision;
 mod cutscene;
 mod debug;
-mod fundsp_kira;
 mod movement;
 mod movement_pointer;
 mod player;
 mod post_process;
-mod sfxr;
-mod simple_bt;
 
 use bevy::{prelude::*, sprite::Anchor};
 // use bevy::sprite::MaterialMesh2dBundle;
@@ -69,11 +66,19 @@ fn main() {
             LdtkPlugin,
             SpawnPlugin,
         ))
+        // The mixer is built along with the audio output
+        .insert_resource(
+            fundsp_kira::mixer::MixerSettings::from_ron(include_str!("../assets/mixer.ron"))
+                .expect("assets/mixer.ron should be a valid mixer"),
+        )
         .add_plugins((
             // Our "subplugins"
+            // Behaviors need to be set up before anyone registers theirs
+            simple_bt::plugin::BehaviorTreePlugin::default(),
+            simple_bt::asset::BehaviorTreeAssetPlugin,
             camera::CameraPlugin,
             debug::DebugPlugin,
-            sfxr::SfxrPlugin,
+            fundsp_kira::sfxr::SfxrPlugin,
             movement::MovementPlugin,
             collision::CollisionPlugin,
             movement_pointer::MovementPointerPlugin,
@@ -148,6 +153,7 @@ impl Plugin for AcerolaGame0 {
                     player::process_player_start,
                     player::handle_dashing,
                     player::move_player,
+                    player::hum_with_speed.after(player::move_player),
                 )
                     .run_if(in_state(GameState::InRun)),
             )
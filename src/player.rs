@@ -3,9 +3,15 @@ use crate::{camera, collision, movement};
 use super::{movement_pointer, PlayerAssets};
 use bevy::prelude::*;
 use bevy_ecs_ldtk::LdtkEntity;
+use fundsp::hacker32::{lowpass_hz, saw};
+use fundsp_kira::spatial::{Attenuation, AudioEmitter};
+use fundsp_kira::Machine;
 use leafwing_input_manager::prelude::*;
 use moonshine_spawn::{spawn_children, SpawnChildren};
 
+const SPEED: f32 = 350.0;
+const DASH_SPEED: f32 = 700.0;
+
 #[derive(Component)]
 pub(crate) struct DashState(pub bool);
 
@@ -40,9 +46,6 @@ pub(crate) fn move_player(
     for (action_state, mut movable, maybe_sprite, dash, maybe_pointer) in query.iter_mut() {
         // Right now, treat our joystick as a velocity.
         if let Some(dad) = action_state.clamped_axis_pair(&PlayerAction::Move) {
-            const SPEED: f32 = 350.0;
-            const DASH_SPEED: f32 = 700.0;
-
             let movement = dad.xy();
 
             if let Some(movement_norm) = movement.try_normalize() {
@@ -76,6 +79,28 @@ pub(crate) fn move_player(
     }
 }
 
+/// A hum that rises in pitch and volume with `speed`, from 0.0 to 1.0
+fn engine_hum() -> Machine {
+    let mut hum = Machine::with_params([("speed", 0.0)], |params| {
+        let pitch = params.var("speed") * 60.0 + 40.0;
+        let volume = params.var("speed") * 0.15 + 0.02;
+        (pitch >> saw() >> lowpass_hz(400.0, 0.7)) * volume
+    });
+    // Keep humming while standing still
+    hum.noise_floor = 0.0;
+    hum
+}
+
+/// Rev the engine hum with how fast the player is moving
+pub(crate) fn hum_with_speed(query: Query<(&movement::Movable, &AudioEmitter), With<Player>>) {
+    for (movable, engine) in query.iter() {
+        if let Some(hum) = engine.playing() {
+            let speed = (movable.velocity.length() / DASH_SPEED).min(1.0);
+            hum.set_param("speed", speed);
+        }
+    }
+}
+
 #[derive(Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect)]
 pub(crate) enum PlayerAction {
     Move,
@@ -114,6 +139,7 @@ pub(crate) struct PlayerBundle {
     dash: DashState,
     moveable: movement::Movable,
     collidable: collision::Collidable,
+    engine: AudioEmitter,
     // moonshine children
     children: SpawnChildren,
 }
@@ -123,6 +149,7 @@ pub(crate) fn process_player_start(
     new_entity_instances: Query<(Entity, &Transform), Added<Player>>,
     // mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut machines: ResMut<Assets<Machine>>,
     player_assets: Res<PlayerAssets>,
 ) {
     for (entity, transform) in new_entity_instances.iter() {
@@ -146,6 +173,9 @@ pub(crate) fn process_player_start(
                 offset: Vec2::Y * 8.0,
                 extents: Vec2::splat(32.0),
             },
+            // The camera follows us, so we're always heard up close
+            engine: AudioEmitter::new(machines.add(engine_hum()))
+                .with_attenuation(Attenuation::None),
             // mesh: MaterialMesh2dBundle {
             //     mesh: meshes
             //         .add(Rectangle {
@@ -1,15 +0,0 @@
-//! Implementations of composite nodes
-
-// We do a little thin runner so nodes are thick
-
-mod inverter;
-mod repeater;
-mod selector;
-mod sequence;
-mod succeeder;
-
-pub use inverter::Inverter;
-pub use repeater::{LimitedRepeated, Repeated, RepeatedUntilFailure};
-pub use selector::Selector;
-pub use sequence::Sequence;
-pub use succeeder::Succeeder;